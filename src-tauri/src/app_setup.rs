@@ -11,6 +11,20 @@ pub fn show_main_window(app: &AppHandle) {
     }
 }
 
+/// Widget sizes (logical px): the pill alone, as in tauri.conf.json, and the pill with
+/// the live transcript above it.
+const WIDGET_SIZE: (f64, f64) = (170.0, 38.0);
+const WIDGET_LIVE_SIZE: (f64, f64) = (420.0, 104.0);
+
+/// Resize the widget for a recording with or without live transcription. Call before
+/// `position_widget_window`, which centers it using the current size.
+pub fn resize_widget_window(window: &WebviewWindow, live: bool) -> Result<(), String> {
+    let (width, height) = if live { WIDGET_LIVE_SIZE } else { WIDGET_SIZE };
+    window
+        .set_size(tauri::Size::Logical(tauri::LogicalSize { width, height }))
+        .map_err(|e| format!("widget resize failed: {e}"))
+}
+
 pub fn position_widget_window(window: &WebviewWindow) -> Result<(), String> {
     if let Some(monitor) = window
         .current_monitor()
@@ -62,6 +76,7 @@ pub fn build_tray(app: &AppHandle) -> Result<(), String> {
                     StatusPayload {
                         status: "idle".to_string(),
                         message: Some("DraVis Flow - local voice transcription".to_string()),
+                        detected_language: None,
                     },
                 );
                 show_main_window(app);
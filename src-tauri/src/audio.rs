@@ -1,12 +1,18 @@
+use crate::config::{normalized_preroll_ms, AudioConfig};
+use crate::dlog;
+use crate::resampler::StreamResampler;
+use crate::ring::{sample_ring, RingConsumer, RingProducer};
+use crate::vad::{
+    extract_voiced, segments_after, voiced_segments, SpeechSegment, VoiceActivityDetector,
+};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{SampleFormat, Stream};
+use cpal::{FromSample, SampleFormat, SizedSample, Stream};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-/// RMS below this level is considered silence. 0.01 is empirically quiet enough to ignore
-/// breath/room noise while detecting any intentional speech.
-const SILENCE_RMS_THRESHOLD: f32 = 0.01;
-
 /// Wrapper to make cpal::Stream usable inside Mutex<InnerState>.
 /// Safety: Stream is only accessed behind a Mutex, so concurrent use is impossible.
 struct SendStream {
@@ -15,31 +21,263 @@ struct SendStream {
 unsafe impl Send for SendStream {}
 unsafe impl Sync for SendStream {}
 
+/// Whisper's input rate. Capture is resampled to this on the consumer thread as it arrives.
+pub(crate) const TARGET_SAMPLE_RATE: u32 = 16_000;
+
+/// Ring capacity in seconds of device-rate mono audio. Generous so a briefly stalled
+/// consumer never forces the callback to drop samples.
+const RING_SECONDS: usize = 2;
+
+/// Frames preallocated for the callback's conversion scratch buffer. Typical callbacks
+/// deliver 256–2048 frames; a larger one grows it once and is reused afterwards.
+const CALLBACK_SCRATCH_FRAMES: usize = 8192;
+
+/// Consumer thread sleep when the ring is empty. Callbacks arrive every ~5–20 ms.
+const CONSUMER_POLL: Duration = Duration::from_millis(5);
+
+/// Minimum spacing between `audio_level` emissions.
+const LEVEL_EMIT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 16 kHz mono audio and where the VAD heard speech in it, as sample indices into
+/// `samples`. For a recording, the voiced regions and the recorder's own segments.
+#[derive(Debug, Clone, Default)]
+pub struct VoicedAudio {
+    pub samples: Vec<f32>,
+    pub speech: Vec<SpeechSegment>,
+}
+
+/// Everything the consumer thread writes: the 16 kHz mono buffer, the VAD that tracks
+/// speech segments over it as chunks arrive, and the resampler feeding both.
+///
+/// Outside a recording (warm mic only) chunks go to the `preroll` ring instead, which
+/// `AudioRecorder::start` moves to the front of the new recording.
+struct CaptureBuffer {
+    samples: Vec<f32>,
+    vad: VoiceActivityDetector,
+    resampler: StreamResampler,
+    /// Reused output buffer for `resampler`, so steady-state callbacks don't allocate.
+    resampled: Vec<f32>,
+    recording: bool,
+    /// Level callback for the current recording; `None` while the mic is only warm.
+    on_level: Option<Arc<dyn Fn(f32) + Send + Sync>>,
+    preroll: VecDeque<f32>,
+    /// Maximum `preroll` length in 16 kHz samples.
+    preroll_len: usize,
+}
+
+impl CaptureBuffer {
+    fn new(preroll_ms: u32) -> Self {
+        let preroll_len = preroll_samples(preroll_ms);
+        Self {
+            samples: Vec::new(),
+            vad: VoiceActivityDetector::new(TARGET_SAMPLE_RATE),
+            resampler: StreamResampler::Passthrough,
+            resampled: Vec::new(),
+            recording: false,
+            on_level: None,
+            preroll: VecDeque::with_capacity(preroll_len),
+            preroll_len,
+        }
+    }
+
+    /// Resample a mono chunk at the device rate and append it to the buffer and the VAD.
+    fn push(&mut self, mono: &[f32]) {
+        self.resampled.clear();
+        self.resampler.process(mono, &mut self.resampled);
+        self.append_resampled();
+    }
+
+    /// Drain the resampler's look-ahead into the buffer. Call before swapping resamplers
+    /// or reading the final buffer.
+    fn flush_resampler(&mut self) {
+        self.resampled.clear();
+        self.resampler.flush(&mut self.resampled);
+        self.append_resampled();
+    }
+
+    fn append_resampled(&mut self) {
+        if self.recording {
+            self.samples.extend_from_slice(&self.resampled);
+            self.vad.process(&self.resampled);
+            return;
+        }
+
+        self.preroll.extend(self.resampled.iter().copied());
+        let excess = self.preroll.len().saturating_sub(self.preroll_len);
+        self.preroll.drain(..excess);
+    }
+
+    /// Start a fresh recording seeded with the pre-roll. Returns the pre-roll length.
+    fn begin_recording(&mut self, on_level: Arc<dyn Fn(f32) + Send + Sync>) -> usize {
+        self.samples.clear();
+        self.samples.extend(self.preroll.drain(..));
+        self.vad = VoiceActivityDetector::new(TARGET_SAMPLE_RATE);
+        self.vad.process(&self.samples);
+        self.recording = true;
+        self.on_level = Some(on_level);
+        self.samples.len()
+    }
+
+    /// End the recording; later chunks go back to the pre-roll.
+    fn end_recording(&mut self) {
+        self.recording = false;
+        self.on_level = None;
+    }
+}
+
+fn preroll_samples(preroll_ms: u32) -> usize {
+    (TARGET_SAMPLE_RATE as u64 * normalized_preroll_ms(preroll_ms) as u64 / 1000) as usize
+}
+
+/// An input device as reported to the frontend by `list_input_devices`.
+#[derive(Debug, Clone, Serialize)]
+pub struct InputDeviceInfo {
+    pub name: String,
+    pub is_default: bool,
+    /// Channel count of the device's default input config (0 if it couldn't be queried).
+    pub channels: u16,
+    /// Sample rate of the device's default input config (0 if it couldn't be queried).
+    pub sample_rate: u32,
+    pub supported_configs: Vec<SupportedInputConfig>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SupportedInputConfig {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub sample_format: String,
+}
+
+/// Enumerate the input devices of the default host.
+pub fn list_input_devices() -> Result<Vec<InputDeviceInfo>, String> {
+    let host = cpal::default_host();
+    let default_name = host.default_input_device().and_then(|d| d.name().ok());
+    let devices = host
+        .input_devices()
+        .map_err(|e| format!("failed to enumerate input devices: {e}"))?;
+
+    let mut out = Vec::new();
+    for device in devices {
+        let Ok(name) = device.name() else {
+            continue;
+        };
+        let (channels, sample_rate) = device
+            .default_input_config()
+            .map(|cfg| (cfg.channels(), cfg.sample_rate().0))
+            .unwrap_or((0, 0));
+        let supported_configs = device
+            .supported_input_configs()
+            .map(|configs| {
+                configs
+                    .map(|range| SupportedInputConfig {
+                        channels: range.channels(),
+                        min_sample_rate: range.min_sample_rate().0,
+                        max_sample_rate: range.max_sample_rate().0,
+                        sample_format: range.sample_format().to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        out.push(InputDeviceInfo {
+            is_default: default_name.as_deref() == Some(name.as_str()),
+            name,
+            channels,
+            sample_rate,
+            supported_configs,
+        });
+    }
+    Ok(out)
+}
+
+/// Find an input device whose name matches `name` (trimmed, case-insensitive).
+fn find_input_device(host: &cpal::Host, name: &str) -> Option<cpal::Device> {
+    let wanted = name.trim();
+    host.input_devices().ok()?.find(|device| {
+        device
+            .name()
+            .map(|n| n.trim().eq_ignore_ascii_case(wanted))
+            .unwrap_or(false)
+    })
+}
+
+/// Thread draining the ring into the `CaptureBuffer`, keeping resampling, VAD, level
+/// metering and the capture lock off the real-time audio thread.
+struct ConsumerThread {
+    stop: Arc<AtomicBool>,
+    handle: std::thread::JoinHandle<()>,
+}
+
+impl ConsumerThread {
+    /// Signal the thread and wait until it has drained the ring.
+    fn finish(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = self.handle.join();
+    }
+}
+
 pub struct AudioRecorder {
     stream: Option<SendStream>,
-    samples: Arc<Mutex<Vec<f32>>>,
+    consumer: Option<ConsumerThread>,
+    capture: Arc<Mutex<CaptureBuffer>>,
+    /// Native rate of the current device.
     sample_rate: u32,
+    /// `[audio] resampler` — "sinc" or "linear".
+    resampler_kind: String,
+    /// Preferred input device name from `[audio] device`. Empty = system default.
+    device_name: String,
     cached_device: Option<cpal::Device>,
     cached_config: Option<cpal::SupportedStreamConfig>,
+    /// True for the whole recording session, even while no stream is open (failed failover).
+    recording: bool,
+    /// When `start` began the current recording.
+    recording_started: Option<Instant>,
+    /// `[audio] warm_mic` — keep the stream open between recordings to fill the pre-roll.
+    warm_mic: bool,
+    /// Set by the stream error callback; polled by the device watcher.
+    stream_failed: Arc<AtomicBool>,
 }
 
 impl AudioRecorder {
-    pub fn new() -> Self {
+    pub fn new(config: &AudioConfig) -> Self {
         let mut recorder = Self {
             stream: None,
-            samples: Arc::new(Mutex::new(Vec::new())),
-            sample_rate: 16_000,
+            consumer: None,
+            capture: Arc::new(Mutex::new(CaptureBuffer::new(config.preroll_ms))),
+            sample_rate: TARGET_SAMPLE_RATE,
+            resampler_kind: config.resampler.clone(),
+            device_name: config.device.trim().to_string(),
             cached_device: None,
             cached_config: None,
+            recording: false,
+            recording_started: None,
+            warm_mic: config.warm_mic,
+            stream_failed: Arc::new(AtomicBool::new(false)),
         };
         recorder.refresh_device();
+        recorder.open_warm_stream();
         recorder
     }
 
-    /// Re-query the default input device and config. Call when the user switches audio inputs.
+    /// Re-query the input device and config. Uses the configured device when it is present,
+    /// otherwise the system default. Call when the user switches audio inputs.
     pub fn refresh_device(&mut self) {
         let host = cpal::default_host();
-        match host.default_input_device() {
+        let preferred = if self.device_name.is_empty() {
+            None
+        } else {
+            let found = find_input_device(&host, &self.device_name);
+            if found.is_none() {
+                dlog!(
+                    "audio: input device '{}' not found, falling back to default",
+                    self.device_name
+                );
+            }
+            found
+        };
+
+        match preferred.or_else(|| host.default_input_device()) {
             Some(device) => match device.default_input_config() {
                 Ok(config) => {
                     self.cached_device = Some(device);
@@ -59,11 +297,78 @@ impl AudioRecorder {
         }
     }
 
+    /// Switch to the input device named `name` (empty = system default) without restarting.
+    pub fn set_device(&mut self, name: &str) -> Result<(), String> {
+        if self.is_recording() {
+            return Err("cannot switch input device while recording".to_string());
+        }
+        self.device_name = name.trim().to_string();
+        if self.stream.is_some() {
+            // Warm stream: move it to the new device.
+            return self.failover();
+        }
+        self.refresh_device();
+        Ok(())
+    }
+
+    /// Turn the warm mic on or off and resize the pre-roll. Takes effect immediately when
+    /// idle; during a recording the stream is left alone until `stop`.
+    pub fn set_warm_mic(&mut self, enabled: bool, preroll_ms: u32) -> Result<(), String> {
+        {
+            let mut lock = self
+                .capture
+                .lock()
+                .map_err(|_| "audio sample buffer lock poisoned".to_string())?;
+            lock.preroll_len = preroll_samples(preroll_ms);
+            lock.preroll.clear();
+        }
+
+        self.warm_mic = enabled;
+        if self.recording {
+            return Ok(());
+        }
+        if enabled {
+            if self.stream.is_none() {
+                self.build_stream()?;
+            }
+        } else {
+            self.close_stream();
+        }
+        Ok(())
+    }
+
+    /// True between `start` and `stop`, including after a failover that found no device.
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Time since the current recording started, `None` when not recording.
+    pub fn recording_elapsed(&self) -> Option<Duration> {
+        self.recording_started.map(|t| t.elapsed())
+    }
+
+    /// True when the mic is kept open between recordings.
+    pub fn warm_mic(&self) -> bool {
+        self.warm_mic
+    }
+
+    /// Name of the device the recorder is currently bound to, if any.
+    pub fn active_device_name(&self) -> Option<String> {
+        self.cached_device.as_ref().and_then(|d| d.name().ok())
+    }
+
+    /// True once the running stream has reported an error (e.g. device unplugged).
+    pub fn stream_failed(&self) -> bool {
+        self.stream_failed.load(Ordering::SeqCst)
+    }
+
+    /// Begin a recording. With the warm mic the stream is already running, so this only
+    /// seeds the recording with the pre-roll; otherwise a stream is built and played.
     pub fn start<F>(&mut self, on_level: F) -> Result<(), String>
     where
         F: Fn(f32) + Send + Sync + 'static,
     {
-        if self.stream.is_some() {
+        if self.is_recording() {
             return Ok(());
         }
 
@@ -72,15 +377,78 @@ impl AudioRecorder {
             self.refresh_device();
         }
 
+        let preroll = {
+            let mut lock = self
+                .capture
+                .lock()
+                .map_err(|_| "audio sample buffer lock poisoned".to_string())?;
+            lock.begin_recording(Arc::new(on_level))
+        };
+        self.recording = true;
+        self.recording_started = Some(Instant::now());
+
+        if self.stream.is_some() {
+            dlog!(
+                "audio: warm start with {} ms of pre-roll",
+                preroll as u64 * 1000 / TARGET_SAMPLE_RATE as u64
+            );
+            return Ok(());
+        }
+
+        let started = self.build_stream();
+        if started.is_err() {
+            self.recording = false;
+            self.recording_started = None;
+            if let Ok(mut lock) = self.capture.lock() {
+                lock.end_recording();
+            }
+        }
+        started
+    }
+
+    /// Move the open stream (a recording or the warm mic) onto the next available device.
+    ///
+    /// Capture is already 16 kHz, so the new device simply keeps appending to the same buffer
+    /// and VAD; the final buffer returned by [`stop`](Self::stop) spans both devices.
+    pub fn failover(&mut self) -> Result<(), String> {
+        if !self.is_recording() && !self.warm_mic {
+            return Ok(());
+        }
+
+        self.close_stream();
+        self.refresh_device();
+        self.build_stream()
+    }
+
+    /// Open the warm stream if it should be running and isn't. Failures are logged; the
+    /// next `start` falls back to opening a stream on demand.
+    fn open_warm_stream(&mut self) {
+        if !self.warm_mic || self.recording || self.stream.is_some() {
+            return;
+        }
+        if let Err(err) = self.build_stream() {
+            dlog!("audio: failed to open warm mic stream: {err}");
+        }
+    }
+
+    /// Drop the cpal stream, then let the consumer drain whatever the callback already wrote.
+    fn close_stream(&mut self) {
+        self.stream.take();
+        if let Some(consumer) = self.consumer.take() {
+            consumer.finish();
+        }
+    }
+
+    /// Build and play an input stream on the cached device, appending to the capture buffer.
+    fn build_stream(&mut self) -> Result<(), String> {
         let device = self
             .cached_device
             .as_ref()
-            .ok_or_else(|| "No default microphone found".to_string())?;
+            .ok_or_else(|| "No microphone found".to_string())?;
         let input_cfg = self
             .cached_config
             .as_ref()
             .ok_or_else(|| "No default input config".to_string())?;
-
         let (channels, sample_rate, cfg) = {
             let channels = input_cfg.channels() as usize;
             let sample_rate = input_cfg.sample_rate().0;
@@ -89,78 +457,31 @@ impl AudioRecorder {
         };
         self.sample_rate = sample_rate;
 
-        let shared_samples = Arc::clone(&self.samples);
-        let on_level: Arc<dyn Fn(f32) + Send + Sync> = Arc::new(on_level);
-        let last_emit = Arc::new(Mutex::new(Instant::now()));
-
         {
             let mut lock = self
-                .samples
+                .capture
                 .lock()
                 .map_err(|_| "audio sample buffer lock poisoned".to_string())?;
-            lock.clear();
+            lock.flush_resampler();
+            lock.resampler =
+                StreamResampler::new(sample_rate, TARGET_SAMPLE_RATE, &self.resampler_kind);
         }
-
-        let err_fn = |err| eprintln!("audio stream error: {err}");
-
-        let sample_format = input_cfg.sample_format();
-        let stream = match sample_format {
-            SampleFormat::F32 => {
-                let samples = Arc::clone(&shared_samples);
-                let level_cb = Arc::clone(&on_level);
-                let emit_clock = Arc::clone(&last_emit);
-                device
-                    .build_input_stream(
-                        &cfg,
-                        move |data: &[f32], _| {
-                            capture_chunk(data, channels, &samples, &level_cb, &emit_clock)
-                        },
-                        err_fn,
-                        None,
-                    )
-                    .map_err(|e| format!("failed to build f32 input stream: {e}"))?
-            }
-            SampleFormat::I16 => {
-                let samples = Arc::clone(&shared_samples);
-                let level_cb = Arc::clone(&on_level);
-                let emit_clock = Arc::clone(&last_emit);
-                device
-                    .build_input_stream(
-                        &cfg,
-                        move |data: &[i16], _| {
-                            let converted: Vec<f32> = data
-                                .iter()
-                                .map(|v| (*v as f32) / (i16::MAX as f32))
-                                .collect();
-                            capture_chunk(&converted, channels, &samples, &level_cb, &emit_clock);
-                        },
-                        err_fn,
-                        None,
-                    )
-                    .map_err(|e| format!("failed to build i16 input stream: {e}"))?
-            }
-            SampleFormat::U16 => {
-                let samples = Arc::clone(&shared_samples);
-                let level_cb = Arc::clone(&on_level);
-                let emit_clock = Arc::clone(&last_emit);
-                device
-                    .build_input_stream(
-                        &cfg,
-                        move |data: &[u16], _| {
-                            let converted: Vec<f32> = data
-                                .iter()
-                                .map(|v| ((*v as f32) / (u16::MAX as f32)) * 2.0 - 1.0)
-                                .collect();
-                            capture_chunk(&converted, channels, &samples, &level_cb, &emit_clock);
-                        },
-                        err_fn,
-                        None,
-                    )
-                    .map_err(|e| format!("failed to build u16 input stream: {e}"))?
-            }
+        self.stream_failed.store(false, Ordering::SeqCst);
+
+        let (producer, ring) = sample_ring(sample_rate as usize * RING_SECONDS);
+        let failed = &self.stream_failed;
+        let stream = match input_cfg.sample_format() {
+            SampleFormat::F32 => build_input::<f32>(device, &cfg, channels, producer, failed)?,
+            SampleFormat::F64 => build_input::<f64>(device, &cfg, channels, producer, failed)?,
+            SampleFormat::I16 => build_input::<i16>(device, &cfg, channels, producer, failed)?,
+            SampleFormat::I32 => build_input::<i32>(device, &cfg, channels, producer, failed)?,
+            SampleFormat::U8 => build_input::<u8>(device, &cfg, channels, producer, failed)?,
+            SampleFormat::U16 => build_input::<u16>(device, &cfg, channels, producer, failed)?,
             sample => return Err(format!("unsupported sample format: {sample:?}")),
         };
 
+        self.consumer = Some(spawn_consumer(ring, Arc::clone(&self.capture))?);
+
         stream
             .play()
             .map_err(|e| format!("failed to start input stream: {e}"))?;
@@ -169,99 +490,196 @@ impl AudioRecorder {
         Ok(())
     }
 
-    pub fn stop(&mut self) -> Result<Vec<f32>, String> {
-        if self.stream.is_none() {
-            return Ok(Vec::new());
+    /// Copy of the current recording from sample `offset` on (16 kHz mono, not VAD-trimmed).
+    /// Empty when not recording. Used by live transcription while the recording grows.
+    pub fn recorded_since(&self, offset: usize) -> Vec<f32> {
+        if !self.recording {
+            return Vec::new();
+        }
+        match self.capture.lock() {
+            Ok(lock) => lock.samples.get(offset..).unwrap_or_default().to_vec(),
+            Err(_) => Vec::new(),
         }
+    }
 
-        self.stream.take();
+    /// Speech the VAD has found in the current recording from sample `offset` on, as
+    /// indices into [`recorded_since(offset)`](Self::recorded_since).
+    pub fn speech_since(&self, offset: usize) -> Vec<SpeechSegment> {
+        if !self.recording {
+            return Vec::new();
+        }
+        match self.capture.lock() {
+            Ok(lock) => segments_after(&lock.vad.segments_so_far(), offset),
+            Err(_) => Vec::new(),
+        }
+    }
 
-        let recorded = self
-            .samples
-            .lock()
-            .map_err(|_| "audio sample buffer lock poisoned".to_string())?
-            .clone();
+    /// Stop capturing and return only the voiced regions (16 kHz mono).
+    /// Returns an empty buffer when the VAD found no speech.
+    ///
+    /// With the warm mic the stream keeps running and refills the pre-roll.
+    pub fn stop(&mut self) -> Result<VoicedAudio, String> {
+        self.stop_from(0)
+    }
 
-        let resampled = if self.sample_rate == 16_000 {
-            recorded
-        } else {
-            resample_linear(&recorded, self.sample_rate, 16_000)
+    /// Like [`stop`](Self::stop), but drops the first `offset` samples before extracting
+    /// the voiced regions — the part live transcription has already committed.
+    pub fn stop_from(&mut self, offset: usize) -> Result<VoicedAudio, String> {
+        if !self.recording {
+            return Ok(VoicedAudio::default());
+        }
+        self.recording = false;
+        self.recording_started = None;
+
+        let keep_stream = self.warm_mic && self.stream.is_some() && !self.stream_failed();
+        if !keep_stream {
+            self.close_stream();
+        }
+
+        let (recorded, segments) = {
+            let mut lock = self
+                .capture
+                .lock()
+                .map_err(|_| "audio sample buffer lock poisoned".to_string())?;
+            if !keep_stream {
+                lock.flush_resampler();
+            }
+            lock.end_recording();
+            let segments = lock.vad.finish();
+            (std::mem::take(&mut lock.samples), segments)
         };
+        self.open_warm_stream();
+
+        let offset = offset.min(recorded.len());
+        let segments = segments_after(&segments, offset);
+        let rest = &recorded[offset..];
+        let voiced = extract_voiced(rest, &segments, TARGET_SAMPLE_RATE);
+        dlog!(
+            "audio: vad kept {} of {} samples across {} speech segments (skipped {offset})",
+            voiced.len(),
+            recorded.len(),
+            segments.len()
+        );
+        Ok(VoicedAudio {
+            samples: voiced,
+            speech: voiced_segments(rest.len(), &segments, TARGET_SAMPLE_RATE),
+        })
+    }
+}
 
-        Ok(trim_silence(&resampled, SILENCE_RMS_THRESHOLD))
+/// Error callback for input streams: log and flag the stream as failed so the device
+/// watcher can fail over to another input.
+fn stream_error_handler(
+    failed: &Arc<AtomicBool>,
+) -> impl FnMut(cpal::StreamError) + Send + 'static {
+    let failed = Arc::clone(failed);
+    move |err| {
+        dlog!("audio stream error: {err}");
+        failed.store(true, Ordering::SeqCst);
     }
 }
 
-/// Trim leading and trailing silence below `threshold` RMS (per 480-sample window ≈ 30ms at 16kHz).
-/// Keeps a tail padding after the last detected speech so trailing words aren't clipped.
-pub(crate) fn trim_silence(samples: &[f32], threshold: f32) -> Vec<f32> {
-    const WINDOW: usize = 480;
-    // Keep ~0.5s of audio after the last voiced window to avoid clipping trailing words
-    const TAIL_PADDING: usize = 16_000 / 2; // 8000 samples = 500ms at 16kHz
-
-    let is_silent = |chunk: &[f32]| -> bool {
-        let sum_sq: f32 = chunk.iter().map(|s| s * s).sum();
-        (sum_sq / chunk.len() as f32).sqrt() < threshold
-    };
-
-    // Find first non-silent window
-    let first_non_silent = samples.chunks(WINDOW).position(|w| !is_silent(w));
-    let start = match first_non_silent {
-        Some(i) => i * WINDOW,
-        None => return Vec::new(), // all windows are silent
-    };
-
-    // Find last non-silent window, then add tail padding
-    let end = samples
-        .chunks(WINDOW)
-        .rposition(|w| !is_silent(w))
-        .map(|i| ((i + 1) * WINDOW + TAIL_PADDING).min(samples.len()))
-        .unwrap_or(samples.len());
-
-    if start >= end {
-        return Vec::new();
-    }
-
-    samples[start..end].to_vec()
+/// Names of the input devices currently visible to the default host.
+pub fn input_device_names() -> Vec<String> {
+    cpal::default_host()
+        .input_devices()
+        .map(|devices| devices.filter_map(|d| d.name().ok()).collect())
+        .unwrap_or_default()
 }
 
-fn capture_chunk(
-    input: &[f32],
+/// Build an input stream for sample type `T`. The callback converts to `f32`, keeps the
+/// first channel, and pushes into the ring — no locks, no allocation after warm-up.
+fn build_input<T>(
+    device: &cpal::Device,
+    cfg: &cpal::StreamConfig,
     channels: usize,
-    sample_buf: &Arc<Mutex<Vec<f32>>>,
-    on_level: &Arc<dyn Fn(f32) + Send + Sync>,
-    last_emit: &Arc<Mutex<Instant>>,
-) {
-    if channels == 0 {
-        return;
-    }
-
-    let mut mono = Vec::with_capacity(input.len() / channels.max(1));
-    for frame in input.chunks(channels) {
-        if let Some(first) = frame.first() {
-            mono.push(*first);
-        }
-    }
+    producer: RingProducer,
+    failed: &Arc<AtomicBool>,
+) -> Result<Stream, String>
+where
+    T: SizedSample + Send + 'static,
+    f32: FromSample<T>,
+{
+    let channels = channels.max(1);
+    let mut scratch: Vec<f32> = Vec::with_capacity(CALLBACK_SCRATCH_FRAMES);
+    device
+        .build_input_stream(
+            cfg,
+            move |data: &[T], _| {
+                scratch.clear();
+                scratch.extend(
+                    data.chunks(channels)
+                        .map(|frame| frame[0].to_sample::<f32>()),
+                );
+                producer.push_slice(&scratch);
+            },
+            stream_error_handler(failed),
+            None,
+        )
+        .map_err(|e| format!("failed to build {} input stream: {e}", T::FORMAT))
+}
 
-    if let Ok(mut lock) = sample_buf.lock() {
-        lock.extend_from_slice(&mono);
-    }
+fn spawn_consumer(
+    ring: RingConsumer,
+    capture: Arc<Mutex<CaptureBuffer>>,
+) -> Result<ConsumerThread, String> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_flag = Arc::clone(&stop);
+
+    let handle = std::thread::Builder::new()
+        .name("audio-consumer".to_string())
+        .spawn(move || {
+            let mut chunk = Vec::with_capacity(CALLBACK_SCRATCH_FRAMES);
+            let mut last_emit = Instant::now();
+            let mut reported_overruns = 0;
+            loop {
+                // Read the flag before draining so nothing written before `finish` is missed.
+                let stopping = stop_flag.load(Ordering::SeqCst);
+
+                chunk.clear();
+                if ring.pop_into(&mut chunk) > 0 {
+                    let on_level = capture.lock().ok().and_then(|mut lock| {
+                        lock.push(&chunk);
+                        lock.on_level.clone()
+                    });
+                    if let Some(on_level) = on_level {
+                        if last_emit.elapsed() >= LEVEL_EMIT_INTERVAL {
+                            last_emit = Instant::now();
+                            (on_level)(rms(&chunk).clamp(0.0, 1.0));
+                        }
+                    }
+                }
 
-    let sum_sq: f32 = mono.iter().map(|v| v * v).sum();
-    let rms = if mono.is_empty() {
-        0.0
-    } else {
-        (sum_sq / mono.len() as f32).sqrt()
-    };
+                let overruns = ring.overruns();
+                if overruns > reported_overruns {
+                    dlog!("audio: ring buffer overrun, {overruns} samples dropped this stream");
+                    reported_overruns = overruns;
+                }
 
-    if let Ok(mut last) = last_emit.lock() {
-        if last.elapsed() >= Duration::from_millis(50) {
-            *last = Instant::now();
-            (on_level)(rms.clamp(0.0, 1.0));
-        }
+                if stopping {
+                    break;
+                }
+                if chunk.is_empty() {
+                    std::thread::sleep(CONSUMER_POLL);
+                }
+            }
+        })
+        .map_err(|e| format!("failed to start audio consumer thread: {e}"))?;
+
+    Ok(ConsumerThread { stop, handle })
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
     }
+    let sum_sq: f32 = samples.iter().map(|v| v * v).sum();
+    (sum_sq / samples.len() as f32).sqrt()
 }
 
+/// One-shot linear resampler this module used before streaming resampling. Kept as the
+/// reference the streaming resamplers are compared against in tests.
+#[cfg(test)]
 pub(crate) fn resample_linear(input: &[f32], in_rate: u32, out_rate: u32) -> Vec<f32> {
     if input.is_empty() || in_rate == out_rate {
         return input.to_vec();
@@ -318,32 +736,24 @@ mod tests {
     }
 
     #[test]
-    fn trim_silence_all_silent() {
-        let samples = vec![0.0f32; 2000];
-        let trimmed = trim_silence(&samples, 0.01);
-        assert!(trimmed.is_empty());
-    }
-
-    #[test]
-    fn trim_silence_preserves_non_silent_middle() {
-        // Build: 480 silent + 480 loud + 480 silent
-        let mut samples = vec![0.0f32; 480];
-        samples.extend(vec![0.5f32; 480]);
-        samples.extend(vec![0.0f32; 480]);
-
-        let trimmed = trim_silence(&samples, 0.01);
-        // Should contain the loud window plus tail padding (up to sample count limit)
-        // The loud window is 480 samples, then tail padding extends into the silent region
-        assert!(trimmed.len() >= 480);
-        assert!(trimmed.len() <= 480 + 480); // can't exceed total trailing samples
-        // First 480 samples must be the loud ones
-        assert!(trimmed[..480].iter().all(|&s| s == 0.5));
-    }
-
-    #[test]
-    fn trim_silence_no_trimming_when_all_loud() {
-        let samples = vec![0.5f32; 960];
-        let trimmed = trim_silence(&samples, 0.01);
-        assert_eq!(trimmed.len(), 960);
+    fn preroll_keeps_only_the_latest_audio_and_seeds_the_recording() {
+        let mut capture = CaptureBuffer::new(100);
+        let cap = preroll_samples(100);
+        let warm: Vec<f32> = (0..cap * 3).map(|i| i as f32).collect();
+        capture.push(&warm);
+        assert!(capture.samples.is_empty());
+        assert_eq!(capture.preroll.len(), cap);
+        assert_eq!(capture.preroll.front().copied(), Some((cap * 2) as f32));
+
+        assert_eq!(capture.begin_recording(Arc::new(|_| {})), cap);
+        capture.push(&[-1.0, -2.0]);
+        assert_eq!(capture.samples.len(), cap + 2);
+        assert_eq!(capture.samples[cap - 1], (cap * 3 - 1) as f32);
+        assert!(capture.preroll.is_empty());
+
+        capture.end_recording();
+        capture.push(&[5.0]);
+        assert_eq!(capture.samples.len(), cap + 2);
+        assert_eq!(capture.preroll.len(), 1);
     }
 }
@@ -0,0 +1,221 @@
+//! Hands-free `auto` recording mode: stop by itself after trailing silence.
+//!
+//! [`SilenceTimer`] is fed the same RMS levels the consumer thread emits as `audio_level`
+//! (one every ~50 ms). Once speech has been heard, a run of quiet levels starts a countdown
+//! shown by the widget, and when the configured silence has elapsed the recording is
+//! stopped as if the hotkey had been tapped. Speech during the countdown cancels it.
+//!
+//! Like the VAD, "quiet" is relative to an adaptive noise floor so a fan or a busy room
+//! doesn't keep the recording open forever.
+
+use crate::pipeline::stop_recording_inner;
+use crate::{dlog, report_pipeline_error};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// How long before the auto-stop the widget starts counting down (capped at the silence).
+const COUNTDOWN: Duration = Duration::from_millis(1000);
+
+/// A level is speech when it exceeds the noise floor by this factor (~10 dB).
+const SPEECH_RATIO: f32 = 3.0;
+
+/// Absolute level below which nothing counts as speech. The widget maps typical speech
+/// to 0.01–0.15 RMS.
+const MIN_SPEECH_LEVEL: f32 = 0.01;
+
+/// The first levels (~200 ms, usually before the user speaks) only seed the noise floor
+/// with their minimum, so steady background noise isn't mistaken for the first word.
+const CALIBRATION_LEVELS: usize = 4;
+
+/// Cap on the calibrated floor in case the user was already talking at the start.
+const MAX_INITIAL_NOISE_FLOOR: f32 = 0.02;
+
+/// Noise floor adaptation per level: fall fast when the room gets quieter, rise slowly on
+/// quiet levels, and creep up during "speech" so steady noise is learned within seconds.
+const NOISE_FALL_RATE: f32 = 0.3;
+const NOISE_RISE_RATE: f32 = 0.05;
+const NOISE_SPEECH_RATE: f32 = 0.002;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AutoStopEvent {
+    /// Silence has lasted long enough to warn; the recording stops after `remaining`.
+    CountdownStarted { remaining: Duration },
+    /// Speech resumed during the countdown.
+    CountdownCancelled,
+    /// Trailing silence reached the configured length. Emitted once.
+    Stop,
+}
+
+pub struct SilenceTimer {
+    silence: Duration,
+    noise_floor: f32,
+    calibrated: usize,
+    /// When the last speech level was seen. `None` until the user starts talking, so a
+    /// slow start never auto-stops an empty recording.
+    last_speech: Option<Instant>,
+    counting_down: bool,
+    stopped: bool,
+}
+
+impl SilenceTimer {
+    pub fn new(silence: Duration) -> Self {
+        Self {
+            silence,
+            noise_floor: MAX_INITIAL_NOISE_FLOOR,
+            calibrated: 0,
+            last_speech: None,
+            counting_down: false,
+            stopped: false,
+        }
+    }
+
+    /// Feed the next level, observed at `now`.
+    pub fn update(&mut self, level: f32, now: Instant) -> Option<AutoStopEvent> {
+        if self.stopped {
+            return None;
+        }
+        if self.calibrated < CALIBRATION_LEVELS {
+            self.calibrated += 1;
+            self.noise_floor = self.noise_floor.min(level);
+            return None;
+        }
+
+        let threshold = (self.noise_floor * SPEECH_RATIO).max(MIN_SPEECH_LEVEL);
+        if level > threshold {
+            self.noise_floor += (level - self.noise_floor) * NOISE_SPEECH_RATE;
+            self.last_speech = Some(now);
+            if self.counting_down {
+                self.counting_down = false;
+                return Some(AutoStopEvent::CountdownCancelled);
+            }
+            return None;
+        }
+
+        let rate = if level < self.noise_floor {
+            NOISE_FALL_RATE
+        } else {
+            NOISE_RISE_RATE
+        };
+        self.noise_floor += (level - self.noise_floor) * rate;
+
+        let quiet_for = now.saturating_duration_since(self.last_speech?);
+        if quiet_for >= self.silence {
+            self.stopped = true;
+            self.counting_down = false;
+            return Some(AutoStopEvent::Stop);
+        }
+
+        let remaining = self.silence - quiet_for;
+        if !self.counting_down && remaining <= COUNTDOWN {
+            self.counting_down = true;
+            return Some(AutoStopEvent::CountdownStarted { remaining });
+        }
+        None
+    }
+}
+
+/// Build the level hook for an `auto` recording. Emits `auto_stop_countdown` with the
+/// remaining milliseconds (or `null` when cancelled) and stops the recording on silence.
+pub fn level_hook(app: AppHandle, silence: Duration) -> impl Fn(f32) + Send + Sync + 'static {
+    let timer = Mutex::new(SilenceTimer::new(silence));
+    move |level| {
+        let event = match timer.lock() {
+            Ok(mut timer) => timer.update(level, Instant::now()),
+            Err(_) => return,
+        };
+
+        match event {
+            Some(AutoStopEvent::CountdownStarted { remaining }) => {
+                let _ = app.emit("auto_stop_countdown", Some(remaining.as_millis() as u64));
+            }
+            Some(AutoStopEvent::CountdownCancelled) => {
+                let _ = app.emit("auto_stop_countdown", None::<u64>);
+            }
+            Some(AutoStopEvent::Stop) => {
+                dlog!("auto mode: {} ms of silence, stopping", silence.as_millis());
+                let app = app.clone();
+                // Stopping joins the consumer thread this hook runs on, so hand it off.
+                tauri::async_runtime::spawn(async move {
+                    if let Err(err) = stop_recording_inner(app.clone()).await {
+                        report_pipeline_error(&app, err);
+                    }
+                });
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK: Duration = Duration::from_millis(50);
+
+    /// Feed `count` identical levels 50 ms apart, returning the events and the next instant.
+    fn feed(
+        timer: &mut SilenceTimer,
+        start: Instant,
+        level: f32,
+        count: usize,
+    ) -> (Vec<AutoStopEvent>, Instant) {
+        let mut now = start;
+        let mut events = Vec::new();
+        for _ in 0..count {
+            events.extend(timer.update(level, now));
+            now += TICK;
+        }
+        (events, now)
+    }
+
+    #[test]
+    fn silence_before_any_speech_never_stops() {
+        let mut timer = SilenceTimer::new(Duration::from_millis(1500));
+        let (events, _) = feed(&mut timer, Instant::now(), 0.001, 200);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn trailing_silence_counts_down_then_stops_once() {
+        let mut timer = SilenceTimer::new(Duration::from_millis(1500));
+        let (events, now) = feed(&mut timer, Instant::now(), 0.08, 20);
+        assert!(events.is_empty());
+
+        let (events, _) = feed(&mut timer, now, 0.002, 60);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events[0],
+            AutoStopEvent::CountdownStarted { remaining } if remaining <= COUNTDOWN
+        ));
+        assert_eq!(events[1], AutoStopEvent::Stop);
+    }
+
+    #[test]
+    fn speech_during_countdown_cancels_it() {
+        let mut timer = SilenceTimer::new(Duration::from_millis(1500));
+        let (_, now) = feed(&mut timer, Instant::now(), 0.08, 10);
+        let (events, now) = feed(&mut timer, now, 0.002, 14);
+        assert!(matches!(
+            events[..],
+            [AutoStopEvent::CountdownStarted { .. }]
+        ));
+
+        let (events, now) = feed(&mut timer, now, 0.08, 1);
+        assert_eq!(events, vec![AutoStopEvent::CountdownCancelled]);
+
+        // The silence window restarts from the latest speech.
+        let (events, _) = feed(&mut timer, now, 0.002, 20);
+        assert!(!events.contains(&AutoStopEvent::Stop));
+    }
+
+    #[test]
+    fn steady_background_noise_counts_as_silence() {
+        let mut timer = SilenceTimer::new(Duration::from_millis(1000));
+        // A fan at 0.012 RMS: above the absolute minimum, but it's the noise floor.
+        let (_, now) = feed(&mut timer, Instant::now(), 0.012, 100);
+        let (_, now) = feed(&mut timer, now, 0.15, 10);
+        let (events, _) = feed(&mut timer, now, 0.012, 40);
+        assert_eq!(events.last(), Some(&AutoStopEvent::Stop));
+    }
+}
@@ -0,0 +1,142 @@
+//! Local Whisper speed benchmark (`benchmark_model`).
+//!
+//! Transcribes one clip at several thread counts and reports the real-time factor of each
+//! run: processing time divided by audio length, so below 1.0 is faster than real time.
+//! Without a file the clip is synthetic — voiced, speech-like tones. Whisper's encoder
+//! cost doesn't depend on what is said, so the timings hold even though the text is noise.
+//! The decoder's cost does: with temperature fallback, unlikely text (which tones produce)
+//! is decoded again a random number of times. So every run uses the `fast` preset, which
+//! never falls back, and an empty prompt, whatever the user's settings.
+
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::config::{decoding_preset, DECODING_PRESET_FAST};
+use crate::dlog;
+use crate::hallucination;
+use crate::state::BenchmarkRun;
+use crate::whisper::{self, TranscribeOptions};
+use std::time::Instant;
+use whisper_rs::WhisperContext;
+
+/// Length of the synthetic clip.
+pub const SYNTHETIC_CLIP_SECS: usize = 10;
+
+/// A thread count this much slower than the fastest run still counts as just as fast, so
+/// the recommendation doesn't take cores for a gain within measurement noise.
+const RECOMMEND_TOLERANCE: f64 = 0.05;
+
+/// Speech-like audio: a 120–180 Hz voice with a few harmonics, switched on and off at a
+/// syllable rate with short pauses between "words".
+pub fn synthetic_clip(secs: usize) -> Vec<f32> {
+    let rate = TARGET_SAMPLE_RATE as f32;
+    let len = secs * TARGET_SAMPLE_RATE as usize;
+    let mut phase = 0.0f32;
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / rate;
+            // Pitch drifts like intonation; syllables at ~4 Hz, a pause every 1.5 s.
+            let pitch = 150.0 + 30.0 * (t * 0.7 * std::f32::consts::TAU).sin();
+            phase += pitch / rate * std::f32::consts::TAU;
+            let syllable = (t * 4.0 * std::f32::consts::TAU).sin().max(0.0);
+            let voiced = if t % 1.5 < 1.2 { syllable } else { 0.0 };
+            let tone = phase.sin() + 0.5 * (2.0 * phase).sin() + 0.25 * (3.0 * phase).sin();
+            0.2 * voiced * tone
+        })
+        .collect()
+}
+
+/// Thread counts to try: powers of two below `cores`, then `cores` itself.
+pub fn thread_candidates(cores: usize) -> Vec<usize> {
+    let cores = cores.max(1);
+    let mut counts: Vec<usize> = std::iter::successors(Some(1usize), |n| Some(n * 2))
+        .take_while(|&n| n < cores)
+        .collect();
+    counts.push(cores);
+    counts
+}
+
+/// Transcribe `audio` once per thread count; `on_run` gets each result as it finishes.
+pub fn run(
+    ctx: &WhisperContext,
+    audio: &[f32],
+    options: &TranscribeOptions,
+    thread_counts: &[usize],
+    on_run: &dyn Fn(&BenchmarkRun),
+) -> Result<Vec<BenchmarkRun>, String> {
+    let clip_secs = audio.len() as f64 / TARGET_SAMPLE_RATE as f64;
+    let speech = hallucination::detect_speech(audio, &options.hallucinations);
+    let mut runs = Vec::new();
+    for &threads in thread_counts {
+        let options = TranscribeOptions {
+            threads,
+            decoding: decoding_preset(DECODING_PRESET_FAST),
+            no_prompt: true,
+            ..options.clone()
+        };
+        let started = Instant::now();
+        whisper::transcribe_with_ctx(ctx, audio, &speech, &options, &|_, _| {})?;
+        let elapsed = started.elapsed();
+        let run = BenchmarkRun {
+            threads,
+            elapsed_ms: elapsed.as_millis() as u64,
+            real_time_factor: elapsed.as_secs_f64() / clip_secs,
+        };
+        dlog!(
+            "benchmark: {threads} threads, {} ms (RTF {:.3})",
+            run.elapsed_ms,
+            run.real_time_factor
+        );
+        on_run(&run);
+        runs.push(run);
+    }
+    Ok(runs)
+}
+
+/// The fewest threads whose run is within [`RECOMMEND_TOLERANCE`] of the fastest.
+pub fn recommend(runs: &[BenchmarkRun]) -> Option<usize> {
+    let fastest = runs
+        .iter()
+        .map(|run| run.real_time_factor)
+        .min_by(f64::total_cmp)?;
+    runs.iter()
+        .filter(|run| run.real_time_factor <= fastest * (1.0 + RECOMMEND_TOLERANCE))
+        .map(|run| run.threads)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(threads: usize, real_time_factor: f64) -> BenchmarkRun {
+        BenchmarkRun {
+            threads,
+            elapsed_ms: 0,
+            real_time_factor,
+        }
+    }
+
+    #[test]
+    fn candidates_are_powers_of_two_up_to_the_core_count() {
+        assert_eq!(thread_candidates(1), [1]);
+        assert_eq!(thread_candidates(6), [1, 2, 4, 6]);
+        assert_eq!(thread_candidates(16), [1, 2, 4, 8, 16]);
+    }
+
+    #[test]
+    fn recommends_fewest_threads_that_are_about_as_fast() {
+        let runs = [run(1, 0.9), run(2, 0.5), run(4, 0.31), run(8, 0.30)];
+        assert_eq!(recommend(&runs), Some(4));
+        assert_eq!(recommend(&[]), None);
+    }
+
+    #[test]
+    fn synthetic_clip_has_speech_and_pauses() {
+        let clip = synthetic_clip(2);
+        assert_eq!(clip.len(), 2 * TARGET_SAMPLE_RATE as usize);
+        assert!(clip.iter().all(|s| s.abs() <= 1.0));
+        // 1.2–1.5 s is a pause.
+        let pause = &clip[(1.25 * 16_000.0) as usize..(1.45 * 16_000.0) as usize];
+        assert!(pause.iter().all(|&s| s == 0.0));
+        assert!(clip[..16_000].iter().any(|&s| s.abs() > 0.1));
+    }
+}
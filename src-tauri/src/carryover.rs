@@ -0,0 +1,86 @@
+//! Context carry-over between dictations.
+//!
+//! A long message is often dictated in several hotkey presses. With `general.carry_context`
+//! the end of the previous dictation conditions Whisper's prompt for the next one, the way
+//! a chunk of a long recording conditions the next chunk (see `whisper`), so names and
+//! casing don't drift between presses. It's only carried when the next recording starts
+//! within `carry_context_secs` of the previous paste, into the same app where the target
+//! app is known (macOS), and in the same translate mode.
+
+use std::time::{Duration, Instant};
+
+/// The last pasted dictation.
+#[derive(Debug, Clone)]
+pub struct LastDictation {
+    /// Formatted text, before Prompt Mode restructures it.
+    pub text: String,
+    pub finished_at: Instant,
+    /// PID of the app it was pasted into, when known.
+    pub app_pid: Option<i32>,
+    pub translate: bool,
+}
+
+/// Text to carry into a recording that starts `now` targeting `app_pid`; `None` when the
+/// previous dictation is too old or went elsewhere. An unknown app on either side doesn't
+/// prevent carrying.
+pub fn carried_text(
+    last: Option<&LastDictation>,
+    now: Instant,
+    max_gap: Duration,
+    app_pid: Option<i32>,
+    translate: bool,
+) -> Option<&str> {
+    let last = last?;
+    let recent = now.saturating_duration_since(last.finished_at) <= max_gap;
+    let same_app = match (last.app_pid, app_pid) {
+        (Some(previous), Some(current)) => previous == current,
+        _ => true,
+    };
+    (recent && same_app && last.translate == translate).then_some(last.text.as_str())
+}
+
+/// `earlier` followed by `text`, for `TranscribeOptions::context`.
+pub fn join_context(earlier: &str, text: &str) -> String {
+    format!("{} {}", earlier.trim(), text.trim())
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carries_recent_text_into_the_same_app_only() {
+        let finished_at = Instant::now();
+        let last = LastDictation {
+            text: "Ask Joana about the Tauri build.".to_string(),
+            finished_at,
+            app_pid: Some(42),
+            translate: false,
+        };
+        let gap = Duration::from_secs(30);
+        let soon = finished_at + Duration::from_secs(10);
+        let carried = |now, pid, translate| carried_text(Some(&last), now, gap, pid, translate);
+
+        assert_eq!(carried(soon, Some(42), false), Some(last.text.as_str()));
+        assert_eq!(carried(soon, None, false), Some(last.text.as_str()));
+        assert_eq!(carried(soon, Some(7), false), None);
+        assert_eq!(carried(soon, Some(42), true), None);
+        assert_eq!(
+            carried(finished_at + Duration::from_secs(31), Some(42), false),
+            None
+        );
+        assert_eq!(carried_text(None, soon, gap, Some(42), false), None);
+    }
+
+    #[test]
+    fn joins_context_without_stray_spaces() {
+        assert_eq!(
+            join_context("First part.", " second part"),
+            "First part. second part"
+        );
+        assert_eq!(join_context("", "only live"), "only live");
+        assert_eq!(join_context("only carried ", ""), "only carried");
+    }
+}
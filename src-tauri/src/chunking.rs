@@ -0,0 +1,212 @@
+//! Long-audio chunking for Whisper.
+//!
+//! Multi-minute buffers are slow to decode in one `state.full` call, need a lot of memory,
+//! and tend to hallucinate near the end. Audio longer than [`CHUNK_THRESHOLD_SECS`] is split
+//! into windows of at most [`CHUNK_MAX_SECS`]: preferably at a pause found by the VAD, which
+//! needs no overlap, otherwise at a fixed point with [`CHUNK_OVERLAP_SECS`] of overlap.
+//! [`stitch`] then drops the words the overlap made Whisper transcribe twice.
+
+use crate::vad::VoiceActivityDetector;
+
+/// Audio up to this long is transcribed in one pass.
+const CHUNK_THRESHOLD_SECS: usize = 45;
+
+/// Longest chunk. Stays under Whisper's native 30 s window.
+const CHUNK_MAX_SECS: usize = 28;
+
+/// A pause closer than this to the chunk start is ignored, so chunks don't get tiny.
+const CHUNK_MIN_SECS: usize = 10;
+
+/// Overlap between chunks cut at a fixed point (no pause found), so no word is split.
+const CHUNK_OVERLAP_SECS: usize = 2;
+
+/// Most words compared when removing the duplicated overlap (~2 s of speech is 5–8 words).
+const STITCH_MAX_WORDS: usize = 16;
+
+/// `[start, end)` sample range of one chunk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chunk {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Split `audio` into chunks. Returns a single chunk for short audio.
+pub fn plan_chunks(audio: &[f32], sample_rate: u32) -> Vec<Chunk> {
+    let rate = sample_rate as usize;
+    let len = audio.len();
+    if len <= CHUNK_THRESHOLD_SECS * rate {
+        return vec![Chunk { start: 0, end: len }];
+    }
+
+    let mut vad = VoiceActivityDetector::new(sample_rate);
+    vad.process(audio);
+    let segments = vad.finish();
+    // Midpoints of the gaps between speech segments.
+    let pauses: Vec<usize> = segments
+        .windows(2)
+        .map(|pair| (pair[0].end + pair[1].start) / 2)
+        .collect();
+
+    let max_len = CHUNK_MAX_SECS * rate;
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while len - start > max_len {
+        let earliest = start + CHUNK_MIN_SECS * rate;
+        let latest = start + max_len;
+        // Latest pause in range keeps chunks as long as allowed.
+        let cut = pauses
+            .iter()
+            .rev()
+            .find(|&&p| p >= earliest && p <= latest)
+            .copied();
+
+        match cut {
+            Some(cut) => {
+                chunks.push(Chunk { start, end: cut });
+                start = cut;
+            }
+            None => {
+                chunks.push(Chunk { start, end: latest });
+                start = latest - CHUNK_OVERLAP_SECS * rate;
+            }
+        }
+    }
+    chunks.push(Chunk { start, end: len });
+    chunks
+}
+
+/// Append `next` to `text`, dropping the longest run of words at the start of `next` that
+/// repeats the end of `text` (compared case- and punctuation-insensitively).
+pub fn stitch(text: &str, next: &str) -> String {
+    let next = next.trim();
+    if text.trim().is_empty() {
+        return next.to_string();
+    }
+    if next.is_empty() {
+        return text.trim().to_string();
+    }
+
+    let prev_words: Vec<&str> = text.split_whitespace().collect();
+    let next_words: Vec<&str> = next.split_whitespace().collect();
+    let max = STITCH_MAX_WORDS.min(prev_words.len()).min(next_words.len());
+
+    let overlap = (1..=max)
+        .rev()
+        .find(|&n| {
+            let tail = &prev_words[prev_words.len() - n..];
+            let head = &next_words[..n];
+            tail.iter()
+                .zip(head)
+                .all(|(a, b)| normalize_word(a) == normalize_word(b))
+        })
+        .unwrap_or(0);
+
+    let rest = next_words[overlap..].join(" ");
+    if rest.is_empty() {
+        text.trim().to_string()
+    } else {
+        format!("{} {rest}", text.trim())
+    }
+}
+
+pub(crate) fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    /// Syllable-like bursts: 250 ms of tone, 100 ms quiet. Gaps are shorter than the VAD
+    /// hangover, so a run of these is one speech segment.
+    fn speech(secs: f32) -> Vec<f32> {
+        let len = (secs * RATE as f32) as usize;
+        let syllable = RATE as usize * 350 / 1000;
+        let voiced = RATE as usize * 250 / 1000;
+        (0..len)
+            .map(|i| {
+                if i % syllable >= voiced {
+                    return 0.0;
+                }
+                (i as f32 * 2.0 * std::f32::consts::PI * 220.0 / RATE as f32).sin() * 0.2
+            })
+            .collect()
+    }
+
+    fn silence(secs: f32) -> Vec<f32> {
+        vec![0.0; (secs * RATE as f32) as usize]
+    }
+
+    #[test]
+    fn short_audio_is_one_chunk() {
+        let audio = speech(30.0);
+        assert_eq!(
+            plan_chunks(&audio, RATE),
+            vec![Chunk {
+                start: 0,
+                end: audio.len()
+            }]
+        );
+    }
+
+    #[test]
+    fn cuts_at_pauses_without_overlap() {
+        let mut audio = Vec::new();
+        for _ in 0..4 {
+            audio.extend(speech(15.0));
+            audio.extend(silence(0.8));
+        }
+        let chunks = plan_chunks(&audio, RATE);
+        assert!(chunks.len() >= 3);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+        // Every cut lands inside one of the 0.8 s pauses.
+        let period = RATE as usize * 158 / 10;
+        let pause = RATE as usize * 15;
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!(chunk.end % period > pause, "cut at {}", chunk.end);
+        }
+        assert_eq!(chunks.last().unwrap().end, audio.len());
+    }
+
+    #[test]
+    fn continuous_speech_uses_fixed_windows_with_overlap() {
+        let audio = speech(70.0);
+        let chunks = plan_chunks(&audio, RATE);
+        let max_len = CHUNK_MAX_SECS * RATE as usize;
+        let overlap = CHUNK_OVERLAP_SECS * RATE as usize;
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|c| c.end - c.start <= max_len));
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].end - pair[1].start, overlap);
+        }
+    }
+
+    #[test]
+    fn stitch_removes_duplicated_overlap() {
+        let joined = stitch(
+            "we shipped the release on Friday and then",
+            "And then we fixed the login bug.",
+        );
+        assert_eq!(
+            joined,
+            "we shipped the release on Friday and then we fixed the login bug."
+        );
+    }
+
+    #[test]
+    fn stitch_keeps_text_without_overlap() {
+        assert_eq!(
+            stitch("First part.", "Second part."),
+            "First part. Second part."
+        );
+        assert_eq!(stitch("", "Only."), "Only.");
+        assert_eq!(stitch("Only.", "  "), "Only.");
+    }
+}
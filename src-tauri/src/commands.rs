@@ -3,20 +3,32 @@
 //! Each `#[tauri::command]` maps to a frontend `invoke()` call.
 //! **Tauri camelCase rule**: Rust `snake_case` params become `camelCase` in JS.
 
+use crate::audio::InputDeviceInfo;
 use crate::config::{
-    default_prompt_model, model_file_path, normalized_model_name, normalized_prompt_provider,
-    save_config, AppConfig,
+    canonical_model_name, check_model_language, check_model_translate, default_prompt_model,
+    expand_home, model_download_url, model_file_path, models_dir, normalized_auto_stop_silence_ms,
+    normalized_carry_context_secs, normalized_decoding_preset,
+    normalized_escalation_min_probability, normalized_language, normalized_model_name,
+    normalized_preroll_ms, normalized_prompt_provider, save_config, AppConfig, LANGUAGE_AUTO,
+    WHISPER_THREADS_MAX,
 };
+use crate::download::run_model_download;
+use crate::models;
 use crate::pipeline::{
-    cancel_recording_inner, run_model_download, start_recording_inner, stop_recording_inner,
+    benchmark_model_inner, cancel_recording_inner, start_recording_inner, stop_recording_inner,
+    transcribe_file_inner, transcribe_options, verify_model_inner,
 };
-use crate::state::{with_state, AppState, ModelStatus};
-use crate::whisper::WhisperEngine;
-use tauri::{AppHandle, State};
+use crate::state::{with_state, AppState, BenchmarkReport, ModelInfo, ModelStatus};
+use crate::whisper::{self, InitialPrompt};
+use crate::{dlog, verify};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
 
 #[tauri::command]
 pub async fn start_recording(app: AppHandle) -> Result<(), String> {
-    start_recording_inner(app).await
+    start_recording_inner(app, None).await
 }
 
 #[tauri::command]
@@ -40,12 +52,19 @@ pub fn get_config(state: State<AppState>) -> Result<AppConfig, String> {
 }
 
 #[tauri::command]
-pub fn set_recording_mode(state: State<AppState>, mode: String) -> Result<AppConfig, String> {
+pub fn set_recording_mode(
+    state: State<AppState>,
+    mode: String,
+    auto_stop_silence_ms: Option<u32>,
+) -> Result<AppConfig, String> {
     let normalized = crate::normalize_recording_mode(&mode)
-        .ok_or_else(|| "mode must be 'hold' or 'toggle'".to_string())?;
+        .ok_or_else(|| "mode must be 'hold', 'toggle' or 'auto'".to_string())?;
 
     with_state(&state, |inner| {
         inner.config.general.mode = normalized.clone();
+        if let Some(ms) = auto_stop_silence_ms {
+            inner.config.general.auto_stop_silence_ms = normalized_auto_stop_silence_ms(ms);
+        }
         inner.toggle_shortcut_held = false;
         save_config(&inner.config)?;
         Ok(inner.config.clone())
@@ -85,11 +104,15 @@ pub fn set_prompt_mode(
 
 #[tauri::command]
 pub fn set_model(state: State<AppState>, name: String) -> Result<ModelStatus, String> {
-    let model_name = normalized_model_name(&name).to_string();
+    let model_name =
+        canonical_model_name(&name).ok_or_else(|| format!("unknown model '{}'", name.trim()))?;
 
     // Update config and invalidate cached WhisperContext
     with_state(&state, |inner| {
+        check_model_language(&model_name, &inner.config.general.language)?;
+        check_model_translate(&model_name, inner.config.general.translate)?;
         inner.config.model.name = model_name.clone();
+        inner.model_valid = None;
         save_config(&inner.config)?;
         Ok(())
     })?;
@@ -100,24 +123,198 @@ pub fn set_model(state: State<AppState>, name: String) -> Result<ModelStatus, St
     }
 
     // Return model status for the new model
+    with_state(&state, |inner| Ok(inner.model_status()))
+}
+
+/// Set the transcription language: a Whisper language code (`"en"`, `"pt"`, ...) or `"auto"`
+/// to detect it per recording. English-only models only accept `"en"`.
+#[tauri::command]
+pub fn set_language(state: State<AppState>, language: String) -> Result<AppConfig, String> {
+    let language = normalized_language(&language);
+    if language != LANGUAGE_AUTO && !whisper::is_known_language(&language) {
+        return Err(format!(
+            "unknown language '{language}'; use a Whisper language code such as 'en' or 'pt', or 'auto'"
+        ));
+    }
+
+    with_state(&state, |inner| {
+        check_model_language(&inner.config.model.name, &language)?;
+        inner.config.general.language = language.clone();
+        save_config(&inner.config)?;
+        Ok(inner.config.clone())
+    })
+}
+
+/// Translate recordings to English (Whisper's translate task). Needs a multilingual model.
+#[tauri::command]
+pub fn set_translate(state: State<AppState>, enabled: bool) -> Result<AppConfig, String> {
+    with_state(&state, |inner| {
+        check_model_translate(&inner.config.model.name, enabled)?;
+        inner.config.general.translate = enabled;
+        save_config(&inner.config)?;
+        Ok(inner.config.clone())
+    })
+}
+
+/// Pick the decoding preset: "fast", "balanced" or "accurate". Settings overridden in
+/// `[decoding]` of config.toml keep their values.
+#[tauri::command]
+pub fn set_decoding_preset(state: State<AppState>, preset: String) -> Result<AppConfig, String> {
+    with_state(&state, |inner| {
+        inner.config.decoding.preset = normalized_decoding_preset(&preset).to_string();
+        save_config(&inner.config)?;
+        Ok(inner.config.clone())
+    })
+}
+
+/// Configure confidence escalation: when a dictation's mean token probability is below
+/// `min_probability`, it's transcribed again with `model` (which has to be downloaded).
+/// `cache_mb` caps the memory of loaded escalation models; a lower cap, or turning
+/// escalation off, unloads them right away.
+#[tauri::command]
+pub async fn set_escalation(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+    model: Option<String>,
+    min_probability: Option<f32>,
+    cache_mb: Option<u32>,
+) -> Result<AppConfig, String> {
+    let config = with_state(&state, |inner| {
+        let escalation = &mut inner.config.escalation;
+        if let Some(model) = model {
+            escalation.model = canonical_model_name(&model)
+                .ok_or_else(|| format!("unknown model '{}'", model.trim()))?;
+        }
+        if let Some(min_probability) = min_probability {
+            escalation.min_probability = normalized_escalation_min_probability(min_probability);
+        }
+        if let Some(cache_mb) = cache_mb {
+            escalation.cache_mb = cache_mb;
+        }
+        escalation.enabled = enabled;
+        save_config(&inner.config)?;
+        Ok(inner.config.clone())
+    })?;
+
+    let capacity_bytes = if config.escalation.enabled {
+        config.escalation.cache_mb as u64 * 1_000_000
+    } else {
+        0
+    };
+    // The cache is locked for a whole escalation; wait for it off the main thread.
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        if let Ok(mut models) = state.escalation_models.lock() {
+            models.set_capacity(capacity_bytes);
+        }
+    })
+    .await
+    .map_err(|e| format!("escalation cache task failed: {e}"))?;
+    Ok(config)
+}
+
+/// Switch the style profile (a `[style.profiles]` entry); empty for none.
+#[tauri::command]
+pub fn set_style_profile(state: State<AppState>, profile: String) -> Result<AppConfig, String> {
     with_state(&state, |inner| {
-        let engine = WhisperEngine::new(&inner.config);
-        Ok(ModelStatus {
-            exists: engine.model_exists(),
-            path: engine.model_path().display().to_string(),
-        })
+        let profile = profile.trim();
+        if !profile.is_empty() && !inner.config.style.profiles.contains_key(profile) {
+            return Err(format!("unknown style profile: {profile}"));
+        }
+        inner.config.style.profile = profile.to_string();
+        save_config(&inner.config)?;
+        Ok(inner.config.clone())
     })
 }
 
 #[tauri::command]
 pub fn check_model(state: State<AppState>) -> Result<ModelStatus, String> {
+    with_state(&state, |inner| Ok(inner.model_status()))
+}
+
+/// Time the active model at several thread counts on an audio file, or a synthetic clip
+/// without one, and recommend a thread count. Emits `benchmark_progress` after each run.
+#[tauri::command]
+pub async fn benchmark_model(
+    app: AppHandle,
+    path: Option<String>,
+) -> Result<BenchmarkReport, String> {
+    let path = path
+        .filter(|path| !path.trim().is_empty())
+        .map(|path| expand_home(&path));
+    benchmark_model_inner(app, path).await
+}
+
+/// Set the CPU threads Whisper uses; 0 = one per available core.
+#[tauri::command]
+pub fn set_whisper_threads(state: State<AppState>, threads: u32) -> Result<AppConfig, String> {
     with_state(&state, |inner| {
-        let engine = WhisperEngine::new(&inner.config);
-        Ok(ModelStatus {
-            exists: engine.model_exists(),
-            path: engine.model_path().display().to_string(),
-        })
+        inner.config.model.threads = threads.min(WHISPER_THREADS_MAX as u32);
+        save_config(&inner.config)?;
+        Ok(inner.config.clone())
+    })
+}
+
+/// Hash the active model and compare it with its expected SHA-256 (see `verify`).
+#[tauri::command]
+pub async fn verify_model(app: AppHandle) -> Result<ModelStatus, String> {
+    verify_model_inner(app).await
+}
+
+/// Every catalog model, whether it's installed (and its size on disk), and which is active.
+#[tauri::command]
+pub fn list_models(state: State<AppState>) -> Result<Vec<ModelInfo>, String> {
+    let (dir, active) = with_state(&state, |inner| {
+        Ok((
+            models_dir(&inner.config),
+            canonical_model_name(&inner.config.model.name),
+        ))
+    })?;
+
+    Ok(models::catalog()
+        .entries()
+        .iter()
+        .map(|entry| model_info(entry, &dir, active.as_deref()))
+        .collect())
+}
+
+fn model_info(entry: &models::ModelEntry, dir: &Path, active: Option<&str>) -> ModelInfo {
+    let disk_bytes = std::fs::metadata(entry.file_path(dir))
+        .map(|meta| meta.len())
+        .ok();
+    ModelInfo {
+        name: entry.name.clone(),
+        filename: entry.filename.clone(),
+        size_mb: entry.size_mb,
+        multilingual: entry.multilingual,
+        quantization: entry.quantization.clone(),
+        installed: disk_bytes.is_some(),
+        disk_bytes: disk_bytes.unwrap_or(0),
+        active: active == Some(entry.name.as_str()),
+    }
+}
+
+/// Add a local model file to the catalog under `name` (default: from the file name).
+/// The file is copied into the models directory, or symlinked with `link`. It must load
+/// as a Whisper model. Use `set_model` afterwards to switch to it.
+#[tauri::command]
+pub async fn import_model(
+    state: State<'_, AppState>,
+    path: String,
+    name: Option<String>,
+    link: Option<bool>,
+) -> Result<ModelInfo, String> {
+    let dir = with_state(&state, |inner| Ok(models_dir(&inner.config)))?;
+    let source = expand_home(&path);
+    let link = link.unwrap_or(false);
+    let entry = tauri::async_runtime::spawn_blocking({
+        let dir = dir.clone();
+        move || models::import_model_file(&source, name.as_deref(), &dir, link)
     })
+    .await
+    .map_err(|e| format!("model import task failed: {e}"))??;
+    Ok(model_info(&entry, &dir, None))
 }
 
 #[tauri::command]
@@ -132,6 +329,26 @@ pub fn set_dictionary_words(
     })
 }
 
+/// Terms that always lead the glossary, in order (see `glossary`).
+#[tauri::command]
+pub fn set_pinned_words(state: State<AppState>, words: Vec<String>) -> Result<AppConfig, String> {
+    with_state(&state, |inner| {
+        inner.config.dictionary.pinned = words;
+        save_config(&inner.config)?;
+        Ok(inner.config.clone())
+    })
+}
+
+/// The initial prompt a dictation would start with now, and which glossary terms it
+/// includes and drops.
+#[tauri::command]
+pub fn preview_initial_prompt(state: State<AppState>) -> Result<InitialPrompt, String> {
+    with_state(&state, |inner| {
+        let options = transcribe_options(inner, inner.config.general.translate);
+        Ok(whisper::initial_prompt(&options))
+    })
+}
+
 #[tauri::command]
 pub fn set_dictionary_replacements(
     state: State<AppState>,
@@ -144,18 +361,149 @@ pub fn set_dictionary_replacements(
     })
 }
 
+#[tauri::command]
+pub fn list_input_devices() -> Result<Vec<InputDeviceInfo>, String> {
+    crate::audio::list_input_devices()
+}
+
+/// Switch the input device at runtime. An empty name selects the system default.
+#[tauri::command]
+pub fn set_audio_device(state: State<AppState>, name: String) -> Result<AppConfig, String> {
+    with_state(&state, |inner| {
+        inner.recorder.set_device(&name)?;
+        inner.config.audio.device = name.trim().to_string();
+        save_config(&inner.config)?;
+        Ok(inner.config.clone())
+    })
+}
+
+/// Turn the warm mic on or off. While on, the microphone stays open (and the OS mic-in-use
+/// indicator stays lit) so `preroll_ms` of audio before the hotkey press is kept.
+#[tauri::command]
+pub fn set_warm_mic(
+    state: State<AppState>,
+    enabled: bool,
+    preroll_ms: Option<u32>,
+) -> Result<AppConfig, String> {
+    with_state(&state, |inner| {
+        let preroll_ms =
+            normalized_preroll_ms(preroll_ms.unwrap_or(inner.config.audio.preroll_ms));
+        inner.recorder.set_warm_mic(enabled, preroll_ms)?;
+        inner.config.audio.warm_mic = enabled;
+        inner.config.audio.preroll_ms = preroll_ms;
+        save_config(&inner.config)?;
+        Ok(inner.config.clone())
+    })
+}
+
+/// Turn live partial transcripts in the widget on or off. Takes effect from the next recording.
+#[tauri::command]
+pub fn set_live_transcription(state: State<AppState>, enabled: bool) -> Result<AppConfig, String> {
+    with_state(&state, |inner| {
+        inner.config.general.live_transcription = enabled;
+        save_config(&inner.config)?;
+        Ok(inner.config.clone())
+    })
+}
+
+/// Turn context carry-over between dictations on or off; `secs` is how soon the next
+/// recording has to start for the previous text to carry over.
+#[tauri::command]
+pub fn set_carry_context(
+    state: State<AppState>,
+    enabled: bool,
+    secs: Option<u32>,
+) -> Result<AppConfig, String> {
+    with_state(&state, |inner| {
+        let general = &mut inner.config.general;
+        general.carry_context = enabled;
+        general.carry_context_secs =
+            normalized_carry_context_secs(secs.unwrap_or(general.carry_context_secs));
+        save_config(&inner.config)?;
+        Ok(inner.config.clone())
+    })
+}
+
+/// Transcribe an audio file (WAV/FLAC/MP3/OGG) and return the formatted text.
+/// Optionally copies it to the clipboard; never pastes at the cursor.
+#[tauri::command]
+pub async fn transcribe_file(
+    app: AppHandle,
+    path: String,
+    copy_to_clipboard: Option<bool>,
+) -> Result<String, String> {
+    transcribe_file_inner(app, PathBuf::from(path), copy_to_clipboard.unwrap_or(false)).await
+}
+
 #[tauri::command]
 pub async fn download_model(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
-    let (model_path, model_name) = with_state(&state, |inner| {
+    let (model_path, model_name, corrupted) = with_state(&state, |inner| {
         Ok((
             model_file_path(&inner.config),
-            inner.config.model.name.clone(),
+            normalized_model_name(&inner.config.model.name),
+            inner.model_valid == Some(false),
         ))
     })?;
 
-    if model_path.exists() {
+    if model_path.exists() && !corrupted {
         return Ok(());
     }
+    let url = model_download_url(&model_name);
+    // Files of `path` entries and imported models are the user's own; never delete them.
+    let user_file = models::catalog().resolve(&model_name).path.is_some();
+    let replace = model_path.exists();
+    if replace && (url.is_empty() || user_file) {
+        return Err(format!(
+            "model '{model_name}' is corrupted and can't be downloaded; re-import it with import_model"
+        ));
+    }
+    if url.is_empty() {
+        return Err(format!("model '{model_name}' has no download URL"));
+    }
+    if state.model_downloading.swap(true, Ordering::SeqCst) {
+        return Err("a model download is already running".to_string());
+    }
+    // Only delete once this call owns the download, so a refused call leaves the file alone.
+    if replace {
+        dlog!(
+            "download: replacing corrupted model {}",
+            model_path.display()
+        );
+        if let Err(err) = verify::remove_model_file(&model_path) {
+            state.model_downloading.store(false, Ordering::SeqCst);
+            return Err(err);
+        }
+    }
+
+    state.download_cancel.store(false, Ordering::SeqCst);
+    let cancel = Arc::clone(&state.download_cancel);
+    let expected_sha256 = models::catalog().resolve(&model_name).sha256.clone();
+    let result = run_model_download(app, model_path, url, expected_sha256, cancel).await;
+    state.model_downloading.store(false, Ordering::SeqCst);
+    if result.is_ok() {
+        with_state(&state, |inner| {
+            // Clears a previous "corrupted"; the next check hashes the new file.
+            if normalized_model_name(&inner.config.model.name) == model_name {
+                inner.model_valid = None;
+            }
+            Ok(())
+        })?;
+        // A context loaded from the replaced file would keep transcribing with it, so drop it
+        // and reload on the next dictation.
+        if corrupted {
+            if let Ok(mut ctx_lock) = state.whisper_ctx.lock() {
+                *ctx_lock = None;
+            }
+        }
+    }
+    result
+}
 
-    run_model_download(app, model_path, model_name).await
+/// Stop the running model download. The partial file is kept, so downloading again resumes.
+#[tauri::command]
+pub fn cancel_model_download(state: State<AppState>) -> Result<(), String> {
+    if state.model_downloading.load(Ordering::SeqCst) {
+        state.download_cancel.store(true, Ordering::SeqCst);
+    }
+    Ok(())
 }
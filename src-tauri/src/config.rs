@@ -1,10 +1,38 @@
+use crate::models;
 use dirs::home_dir;
 use serde::{Deserialize, Serialize};
-use std::{fs, io::Write, path::PathBuf};
+use std::{collections::BTreeMap, fs, io::Write, path::PathBuf};
 
 pub const MODEL_BASE_EN: &str = "base.en";
-pub const MODEL_SMALL_EN: &str = "small.en";
 pub const MODEL_LARGE_V3_TURBO: &str = "large-v3-turbo";
+/// `general.language` value that has Whisper detect the spoken language.
+pub const LANGUAGE_AUTO: &str = "auto";
+pub const RESAMPLER_SINC: &str = "sinc";
+pub const RESAMPLER_LINEAR: &str = "linear";
+pub const AUTO_STOP_SILENCE_MS_DEFAULT: u32 = 2000;
+pub const AUTO_STOP_SILENCE_MS_MIN: u32 = 500;
+pub const AUTO_STOP_SILENCE_MS_MAX: u32 = 10_000;
+pub const MAX_RECORDING_SECS_DEFAULT: u32 = 300;
+pub const MAX_RECORDING_SECS_MIN: u32 = 30;
+pub const MAX_RECORDING_SECS_MAX: u32 = 3600;
+pub const CARRY_CONTEXT_SECS_DEFAULT: u32 = 30;
+pub const CARRY_CONTEXT_SECS_MIN: u32 = 1;
+pub const CARRY_CONTEXT_SECS_MAX: u32 = 600;
+pub const PREROLL_MS_DEFAULT: u32 = 400;
+pub const PREROLL_MS_MIN: u32 = 100;
+pub const PREROLL_MS_MAX: u32 = 1000;
+pub const DECODING_PRESET_FAST: &str = "fast";
+pub const DECODING_PRESET_BALANCED: &str = "balanced";
+pub const DECODING_PRESET_ACCURATE: &str = "accurate";
+pub const BEAM_SIZE_MAX: u32 = 16;
+pub const WHISPER_THREADS_MAX: usize = 64;
+pub const HALLUCINATION_MAX_REPEATS_DEFAULT: u32 = 2;
+pub const ESCALATION_MIN_PROBABILITY_DEFAULT: f32 = 0.7;
+pub const ESCALATION_CACHE_MB_DEFAULT: u32 = 2048;
+/// `dictionary.ranking`: most-used terms first (see `glossary`).
+pub const GLOSSARY_RANKING_USAGE: &str = "usage";
+/// `dictionary.ranking`: terms in the order they're listed.
+pub const GLOSSARY_RANKING_CONFIG: &str = "config";
 pub const PROMPT_PROVIDER_ANTHROPIC: &str = "anthropic";
 pub const PROMPT_PROVIDER_OPENAI: &str = "openai";
 pub const PROMPT_PROVIDER_OPENROUTER: &str = "openrouter";
@@ -17,12 +45,54 @@ pub struct GeneralConfig {
     pub language: String,
     pub hotkey: String,
     pub mode: String,
+    /// Trailing silence (ms) that ends a recording in `auto` mode.
+    #[serde(default = "default_auto_stop_silence_ms")]
+    pub auto_stop_silence_ms: u32,
+    /// Recordings are stopped and transcribed automatically after this many seconds.
+    #[serde(default = "default_max_recording_secs")]
+    pub max_recording_secs: u32,
+    /// Show partial transcripts in the widget while recording. Costs CPU/GPU while the
+    /// recording runs, but the final pass only has to transcribe the last few seconds.
+    #[serde(default)]
+    pub live_transcription: bool,
+    /// Translate speech in any language to English (Whisper's translate task) instead of
+    /// transcribing it. Needs a multilingual model.
+    #[serde(default)]
+    pub translate: bool,
+    /// Optional second hotkey whose recordings are always translated, e.g.
+    /// `"ctrl+shift+t"`. Empty = none. Read at startup.
+    #[serde(default)]
+    pub translate_hotkey: String,
+    /// Condition Whisper on the end of the previous dictation when the next recording
+    /// starts within `carry_context_secs` (and in the same app, where that's known).
+    #[serde(default)]
+    pub carry_context: bool,
+    #[serde(default = "default_carry_context_secs")]
+    pub carry_context_secs: u32,
+}
+
+fn default_auto_stop_silence_ms() -> u32 {
+    AUTO_STOP_SILENCE_MS_DEFAULT
+}
+
+fn default_max_recording_secs() -> u32 {
+    MAX_RECORDING_SECS_DEFAULT
+}
+
+fn default_carry_context_secs() -> u32 {
+    CARRY_CONTEXT_SECS_DEFAULT
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelConfig {
     pub name: String,
     pub path: String,
+    /// Check the model's SHA-256 in the background on startup.
+    #[serde(default)]
+    pub verify_on_startup: bool,
+    /// CPU threads Whisper runs on; 0 = one per available core. `benchmark_model` suggests a value.
+    #[serde(default)]
+    pub threads: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,7 +143,7 @@ impl PromptModeConfig {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DictionaryConfig {
     /// Words/terms Whisper should recognize (fed into initial_prompt as glossary)
     #[serde(default)]
@@ -81,6 +151,337 @@ pub struct DictionaryConfig {
     /// Post-transcription replacements: "from -> to"
     #[serde(default)]
     pub replacements: Vec<ReplacementEntry>,
+    /// Terms that always lead the glossary, in this order, so they're never cut off.
+    #[serde(default)]
+    pub pinned: Vec<String>,
+    /// Order of the other glossary terms: "usage" (most used first) or "config".
+    #[serde(default = "default_glossary_ranking")]
+    pub ranking: String,
+}
+
+fn default_glossary_ranking() -> String {
+    GLOSSARY_RANKING_USAGE.to_string()
+}
+
+impl Default for DictionaryConfig {
+    fn default() -> Self {
+        Self {
+            words: Vec::new(),
+            replacements: Vec::new(),
+            pinned: Vec::new(),
+            ranking: default_glossary_ranking(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioConfig {
+    /// Input device name as reported by `list_input_devices`. Empty = system default.
+    #[serde(default)]
+    pub device: String,
+    /// "sinc" (band-limited, default) or "linear" (faster, no anti-aliasing).
+    #[serde(default = "default_resampler")]
+    pub resampler: String,
+    /// Keep the microphone open between dictations so the moment before the hotkey press
+    /// can be prepended. The OS mic-in-use indicator stays on the whole time the app runs.
+    #[serde(default)]
+    pub warm_mic: bool,
+    /// How much audio before the hotkey press is kept when `warm_mic` is on.
+    #[serde(default = "default_preroll_ms")]
+    pub preroll_ms: u32,
+}
+
+fn default_resampler() -> String {
+    RESAMPLER_SINC.to_string()
+}
+
+fn default_preroll_ms() -> u32 {
+    PREROLL_MS_DEFAULT
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            device: String::new(),
+            resampler: default_resampler(),
+            warm_mic: false,
+            preroll_ms: default_preroll_ms(),
+        }
+    }
+}
+
+/// How Whisper decodes. `preset` picks the settings; any other field set here overrides
+/// that one setting of the preset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecodingConfig {
+    /// "fast", "balanced" (default) or "accurate".
+    #[serde(default = "default_decoding_preset")]
+    pub preset: String,
+    /// Beam search width; 1 decodes greedily.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub beam_size: Option<u32>,
+    /// Sampling temperature of the first attempt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Temperature added on each retry when a segment fails the thresholds below; 0 = no retries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature_inc: Option<f32>,
+    /// Retry a segment whose compression-ratio entropy is above this (repetition loops).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entropy_thold: Option<f32>,
+    /// Retry a segment whose average token log probability is below this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprob_thold: Option<f32>,
+    /// Treat a segment as silence when the no-speech probability is above this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_speech_thold: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suppress_blank: Option<bool>,
+    /// Suppress tokens that aren't speech, such as `[MUSIC]` or `(laughs)`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suppress_non_speech_tokens: Option<bool>,
+}
+
+fn default_decoding_preset() -> String {
+    DECODING_PRESET_BALANCED.to_string()
+}
+
+impl Default for DecodingConfig {
+    fn default() -> Self {
+        Self {
+            preset: default_decoding_preset(),
+            beam_size: None,
+            temperature: None,
+            temperature_inc: None,
+            entropy_thold: None,
+            logprob_thold: None,
+            no_speech_thold: None,
+            suppress_blank: None,
+            suppress_non_speech_tokens: None,
+        }
+    }
+}
+
+/// Resolved Whisper decoding parameters (see [`DecodingConfig::settings`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecodingSettings {
+    pub beam_size: u32,
+    pub temperature: f32,
+    pub temperature_inc: f32,
+    pub entropy_thold: f32,
+    pub logprob_thold: f32,
+    pub no_speech_thold: f32,
+    pub suppress_blank: bool,
+    pub suppress_non_speech_tokens: bool,
+}
+
+impl Default for DecodingSettings {
+    fn default() -> Self {
+        decoding_preset(DECODING_PRESET_BALANCED)
+    }
+}
+
+/// Settings of a preset; unknown names get "balanced".
+///
+/// - fast: greedy, no temperature fallback — one decoder pass per segment.
+/// - balanced: greedy, falling back to higher temperatures on repetitive or unlikely
+///   segments (whisper.cpp's defaults).
+/// - accurate: beam search of 5 with the same fallback; several times slower.
+pub fn decoding_preset(preset: &str) -> DecodingSettings {
+    let balanced = DecodingSettings {
+        beam_size: 1,
+        temperature: 0.0,
+        temperature_inc: 0.2,
+        entropy_thold: 2.4,
+        logprob_thold: -1.0,
+        no_speech_thold: 0.6,
+        suppress_blank: true,
+        suppress_non_speech_tokens: true,
+    };
+    match normalized_decoding_preset(preset) {
+        DECODING_PRESET_FAST => DecodingSettings {
+            temperature_inc: 0.0,
+            ..balanced
+        },
+        DECODING_PRESET_ACCURATE => DecodingSettings {
+            beam_size: 5,
+            ..balanced
+        },
+        _ => balanced,
+    }
+}
+
+impl DecodingConfig {
+    /// The preset's settings with this config's overrides applied and clamped to sane ranges.
+    pub fn settings(&self) -> DecodingSettings {
+        let preset = decoding_preset(&self.preset);
+        DecodingSettings {
+            beam_size: self
+                .beam_size
+                .unwrap_or(preset.beam_size)
+                .clamp(1, BEAM_SIZE_MAX),
+            temperature: self
+                .temperature
+                .unwrap_or(preset.temperature)
+                .clamp(0.0, 1.0),
+            temperature_inc: self
+                .temperature_inc
+                .unwrap_or(preset.temperature_inc)
+                .clamp(0.0, 1.0),
+            entropy_thold: self.entropy_thold.unwrap_or(preset.entropy_thold),
+            logprob_thold: self.logprob_thold.unwrap_or(preset.logprob_thold),
+            no_speech_thold: self
+                .no_speech_thold
+                .unwrap_or(preset.no_speech_thold)
+                .clamp(0.0, 1.0),
+            suppress_blank: self.suppress_blank.unwrap_or(preset.suppress_blank),
+            suppress_non_speech_tokens: self
+                .suppress_non_speech_tokens
+                .unwrap_or(preset.suppress_non_speech_tokens),
+        }
+    }
+}
+
+/// The style sentence that opens Whisper's prompt. Whisper imitates its punctuation,
+/// casing and register, so it should read like the text you want back.
+///
+/// Lookup for a language: the active profile's sentence for it, then the profile's
+/// `prompt`, then `languages` and `prompt` here, then the built-in sentence for the
+/// language (see `whisper`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StyleConfig {
+    /// Active entry of `profiles`; empty = none.
+    #[serde(default)]
+    pub profile: String,
+    /// Sentence for every language without its own in `languages`; empty = built-in.
+    #[serde(default)]
+    pub prompt: String,
+    /// Sentence per Whisper language code, e.g. `pt = "..."`.
+    #[serde(default)]
+    pub languages: BTreeMap<String, String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, StyleProfile>,
+}
+
+/// A named set of style sentences, e.g. casual chat vs. formal email.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StyleProfile {
+    #[serde(default)]
+    pub prompt: String,
+    #[serde(default)]
+    pub languages: BTreeMap<String, String>,
+}
+
+impl StyleConfig {
+    /// Configured style sentence for `language`; `None` means use the built-in one.
+    pub fn style_prompt(&self, language: &str) -> Option<&str> {
+        let profile = self.profiles.get(self.profile.trim());
+        let candidates = [
+            profile.and_then(|p| p.languages.get(language)),
+            profile.map(|p| &p.prompt),
+            self.languages.get(language),
+            Some(&self.prompt),
+        ];
+        candidates
+            .into_iter()
+            .flatten()
+            .map(|prompt| prompt.trim())
+            .find(|prompt| !prompt.is_empty())
+    }
+}
+
+/// Removing text Whisper made up from its output (see `hallucination`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HallucinationConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Sentences that are dropped wherever they appear, compared ignoring case and
+    /// punctuation.
+    #[serde(default = "default_hallucination_phrases")]
+    pub phrases: Vec<String>,
+    /// Identical segments or sentences in a row that are kept; a longer run is a loop and
+    /// collapses to one.
+    #[serde(default = "default_hallucination_max_repeats")]
+    pub max_repeats: u32,
+    /// Drop segments over audio where the VAD hears no speech.
+    #[serde(default = "default_true")]
+    pub drop_without_speech: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Lines Whisper picked up from video subtitles and produces on silence or noise.
+fn default_hallucination_phrases() -> Vec<String> {
+    [
+        "Thank you for watching.",
+        "Thanks for watching!",
+        "Thank you for watching and see you next time.",
+        "Please subscribe to my channel.",
+        "Don't forget to like and subscribe.",
+        "See you in the next video.",
+        "Subtitles by the Amara.org community",
+        "Transcription by CastingWords",
+    ]
+    .iter()
+    .map(|phrase| phrase.to_string())
+    .collect()
+}
+
+fn default_hallucination_max_repeats() -> u32 {
+    HALLUCINATION_MAX_REPEATS_DEFAULT
+}
+
+impl Default for HallucinationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            phrases: default_hallucination_phrases(),
+            max_repeats: default_hallucination_max_repeats(),
+            drop_without_speech: true,
+        }
+    }
+}
+
+/// Re-transcribing unclear dictations with a larger model (see `pipeline`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscalationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Catalog name of the model to re-transcribe with; it has to be downloaded.
+    #[serde(default = "default_escalation_model")]
+    pub model: String,
+    /// Re-transcribe when the mean token probability is below this (0–1).
+    #[serde(default = "default_escalation_min_probability")]
+    pub min_probability: f32,
+    /// Memory the cached escalation models may take, in MB (estimated from their file
+    /// sizes). The least recently used one is unloaded to make room.
+    #[serde(default = "default_escalation_cache_mb")]
+    pub cache_mb: u32,
+}
+
+fn default_escalation_model() -> String {
+    MODEL_LARGE_V3_TURBO.to_string()
+}
+
+fn default_escalation_min_probability() -> f32 {
+    ESCALATION_MIN_PROBABILITY_DEFAULT
+}
+
+fn default_escalation_cache_mb() -> u32 {
+    ESCALATION_CACHE_MB_DEFAULT
+}
+
+impl Default for EscalationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: default_escalation_model(),
+            min_probability: default_escalation_min_probability(),
+            cache_mb: default_escalation_cache_mb(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,6 +499,16 @@ pub struct AppConfig {
     pub prompt_mode: PromptModeConfig,
     #[serde(default)]
     pub dictionary: DictionaryConfig,
+    #[serde(default)]
+    pub audio: AudioConfig,
+    #[serde(default)]
+    pub decoding: DecodingConfig,
+    #[serde(default)]
+    pub style: StyleConfig,
+    #[serde(default)]
+    pub hallucinations: HallucinationConfig,
+    #[serde(default)]
+    pub escalation: EscalationConfig,
 }
 
 impl Default for AppConfig {
@@ -107,10 +518,19 @@ impl Default for AppConfig {
                 language: "en".to_string(),
                 hotkey: "ctrl+shift+space".to_string(),
                 mode: "hold".to_string(),
+                auto_stop_silence_ms: default_auto_stop_silence_ms(),
+                max_recording_secs: default_max_recording_secs(),
+                live_transcription: false,
+                translate: false,
+                translate_hotkey: String::new(),
+                carry_context: false,
+                carry_context_secs: default_carry_context_secs(),
             },
             model: ModelConfig {
                 name: MODEL_BASE_EN.to_string(),
                 path: "~/.dravis-flow/models/".to_string(),
+                verify_on_startup: false,
+                threads: 0,
             },
             formatting: FormattingConfig {
                 level: "basic".to_string(),
@@ -188,7 +608,14 @@ impl Default for AppConfig {
                 .map(String::from)
                 .collect(),
                 replacements: vec![],
+                pinned: vec![],
+                ranking: default_glossary_ranking(),
             },
+            audio: AudioConfig::default(),
+            decoding: DecodingConfig::default(),
+            style: StyleConfig::default(),
+            hallucinations: HallucinationConfig::default(),
+            escalation: EscalationConfig::default(),
         }
     }
 }
@@ -200,7 +627,12 @@ pub fn config_dir() -> PathBuf {
 }
 
 pub fn models_dir(config: &AppConfig) -> PathBuf {
-    let path = config.model.path.trim();
+    expand_home(&config.model.path)
+}
+
+/// `path` with a leading `~/` replaced by the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    let path = path.trim();
     if let Some(stripped) = path.strip_prefix("~/") {
         return home_dir()
             .unwrap_or_else(|| PathBuf::from("."))
@@ -209,37 +641,59 @@ pub fn models_dir(config: &AppConfig) -> PathBuf {
     PathBuf::from(path)
 }
 
-pub fn canonical_model_name(name: &str) -> Option<&'static str> {
-    match name.trim() {
-        MODEL_BASE_EN => Some(MODEL_BASE_EN),
-        MODEL_SMALL_EN => Some(MODEL_SMALL_EN),
-        MODEL_LARGE_V3_TURBO => Some(MODEL_LARGE_V3_TURBO),
-        _ => None,
-    }
+/// Catalog name for `name` (see `models`), or `None` for a model the catalog doesn't list.
+pub fn canonical_model_name(name: &str) -> Option<String> {
+    models::catalog().find(name).map(|entry| entry.name.clone())
+}
+
+/// Catalog name for `name`, falling back to the default model for unknown names.
+pub fn normalized_model_name(name: &str) -> String {
+    models::catalog().resolve(name).name.clone()
 }
 
-pub fn normalized_model_name(name: &str) -> &'static str {
-    canonical_model_name(name).unwrap_or(MODEL_BASE_EN)
+/// Download URL of the model; empty if the catalog entry has none.
+pub fn model_download_url(model_name: &str) -> String {
+    models::catalog().resolve(model_name).url.clone()
 }
 
-pub fn model_filename(model_name: &str) -> &'static str {
-    match normalized_model_name(model_name) {
-        MODEL_SMALL_EN => "ggml-small.en.bin",
-        MODEL_LARGE_V3_TURBO => "ggml-large-v3-turbo.bin",
-        _ => "ggml-base.en.bin",
+/// English-only models (`*.en`) can't transcribe other languages or detect the language.
+pub fn is_english_only_model(model_name: &str) -> bool {
+    !models::catalog().resolve(model_name).multilingual
+}
+
+/// Reject translation on an English-only model, which has no translate task.
+pub fn check_model_translate(model_name: &str, translate: bool) -> Result<(), String> {
+    let model = normalized_model_name(model_name);
+    if translate && is_english_only_model(&model) {
+        return Err(format!(
+            "{model} is an English-only model and can't translate. \
+             Pick a multilingual model (base, small, medium or large-v3-turbo) to translate to English."
+        ));
     }
+    Ok(())
 }
 
-pub fn model_download_url(model_name: &str) -> &'static str {
-    match normalized_model_name(model_name) {
-        MODEL_SMALL_EN => {
-            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.en.bin"
-        }
-        MODEL_LARGE_V3_TURBO => {
-            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo.bin"
-        }
-        _ => "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.en.bin",
+/// Trimmed, lowercase language code (`"en"`, `"pt"`, ...) or [`LANGUAGE_AUTO`].
+pub fn normalized_language(language: &str) -> String {
+    language.trim().to_lowercase()
+}
+
+/// Reject a language setting the model can't serve: anything but English on a `.en` model.
+pub fn check_model_language(model_name: &str, language: &str) -> Result<(), String> {
+    let model = normalized_model_name(model_name);
+    let language = normalized_language(language);
+    if !is_english_only_model(&model) || language == "en" {
+        return Ok(());
     }
+    let wanted = if language == LANGUAGE_AUTO {
+        "automatic language detection".to_string()
+    } else {
+        format!("language '{language}'")
+    };
+    Err(format!(
+        "{model} is an English-only model and can't be used with {wanted}. \
+         Pick a multilingual model (base, small, medium or large-v3-turbo) or set the language to 'en'."
+    ))
 }
 
 pub fn normalized_prompt_provider(provider: &str) -> &'static str {
@@ -261,9 +715,67 @@ pub fn default_prompt_model(provider: &str) -> &'static str {
     }
 }
 
+pub fn normalized_glossary_ranking(ranking: &str) -> &'static str {
+    if ranking.trim().eq_ignore_ascii_case(GLOSSARY_RANKING_CONFIG) {
+        GLOSSARY_RANKING_CONFIG
+    } else {
+        GLOSSARY_RANKING_USAGE
+    }
+}
+
+pub fn normalized_decoding_preset(preset: &str) -> &'static str {
+    match preset.trim().to_lowercase().as_str() {
+        DECODING_PRESET_FAST => DECODING_PRESET_FAST,
+        DECODING_PRESET_ACCURATE => DECODING_PRESET_ACCURATE,
+        _ => DECODING_PRESET_BALANCED,
+    }
+}
+
+pub fn normalized_auto_stop_silence_ms(ms: u32) -> u32 {
+    ms.clamp(AUTO_STOP_SILENCE_MS_MIN, AUTO_STOP_SILENCE_MS_MAX)
+}
+
+pub fn normalized_max_recording_secs(secs: u32) -> u32 {
+    secs.clamp(MAX_RECORDING_SECS_MIN, MAX_RECORDING_SECS_MAX)
+}
+
+pub fn normalized_carry_context_secs(secs: u32) -> u32 {
+    secs.clamp(CARRY_CONTEXT_SECS_MIN, CARRY_CONTEXT_SECS_MAX)
+}
+
+pub fn normalized_escalation_min_probability(probability: f32) -> f32 {
+    if probability.is_nan() {
+        return ESCALATION_MIN_PROBABILITY_DEFAULT;
+    }
+    probability.clamp(0.0, 1.0)
+}
+
+pub fn normalized_preroll_ms(ms: u32) -> u32 {
+    ms.clamp(PREROLL_MS_MIN, PREROLL_MS_MAX)
+}
+
+/// Threads to run Whisper on for the configured `threads` (0 = all available cores).
+pub fn whisper_threads(configured: u32) -> usize {
+    let threads = match configured {
+        0 => std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4),
+        n => n as usize,
+    };
+    threads.clamp(1, WHISPER_THREADS_MAX)
+}
+
 pub fn model_file_path(config: &AppConfig) -> PathBuf {
-    let base = models_dir(config);
-    base.join(model_filename(&config.model.name))
+    models::catalog()
+        .resolve(&config.model.name)
+        .file_path(&models_dir(config))
+}
+
+/// File of the `[escalation]` model, in the same directory as the main model.
+pub fn escalation_model_path(config: &AppConfig) -> PathBuf {
+    models::catalog()
+        .resolve(&config.escalation.model)
+        .file_path(&models_dir(config))
 }
 
 pub fn config_path() -> PathBuf {
@@ -309,10 +821,11 @@ mod tests {
 
     #[test]
     fn maps_model_names_to_expected_files_and_urls() {
-        assert_eq!(model_filename(MODEL_BASE_EN), "ggml-base.en.bin");
-        assert_eq!(model_filename(MODEL_SMALL_EN), "ggml-small.en.bin");
+        let catalog = models::catalog();
+        assert_eq!(catalog.resolve(MODEL_BASE_EN).filename, "ggml-base.en.bin");
+        assert_eq!(catalog.resolve("small.en").filename, "ggml-small.en.bin");
         assert_eq!(
-            model_filename(MODEL_LARGE_V3_TURBO),
+            catalog.resolve("large-v3-turbo").filename,
             "ggml-large-v3-turbo.bin"
         );
 
@@ -321,21 +834,194 @@ mod tests {
             "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.en.bin"
         );
         assert_eq!(
-            model_download_url(MODEL_SMALL_EN),
+            model_download_url("small.en"),
             "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.en.bin"
         );
         assert_eq!(
-            model_download_url(MODEL_LARGE_V3_TURBO),
+            model_download_url("large-v3-turbo"),
             "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo.bin"
         );
     }
 
+    #[test]
+    fn maps_multilingual_models() {
+        assert_eq!(
+            models::catalog().resolve("medium").filename,
+            "ggml-medium.bin"
+        );
+        assert_eq!(
+            model_download_url("small"),
+            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin"
+        );
+        assert_eq!(normalized_model_name(" base "), "base");
+    }
+
+    #[test]
+    fn english_only_models_reject_other_languages() {
+        assert!(check_model_language(MODEL_BASE_EN, "en").is_ok());
+        assert!(check_model_language(MODEL_BASE_EN, " EN ").is_ok());
+        assert!(check_model_language("small.en", "pt").is_err());
+        let err = check_model_language(MODEL_BASE_EN, LANGUAGE_AUTO).unwrap_err();
+        assert!(err.contains("automatic language detection"), "{err}");
+        assert!(check_model_language("base", "pt").is_ok());
+        assert!(check_model_language("large-v3-turbo", LANGUAGE_AUTO).is_ok());
+    }
+
+    #[test]
+    fn translation_needs_a_multilingual_model() {
+        assert!(check_model_translate(MODEL_BASE_EN, false).is_ok());
+        assert!(check_model_translate("small.en", true).is_err());
+        assert!(check_model_translate("medium", true).is_ok());
+    }
+
     #[test]
     fn config_toml_roundtrip_keeps_model_name() {
         let mut config = AppConfig::default();
-        config.model.name = MODEL_SMALL_EN.to_string();
+        config.model.name = "small.en".to_string();
         let encoded = toml::to_string_pretty(&config).expect("serialize config");
         let decoded = toml::from_str::<AppConfig>(&encoded).expect("parse config");
-        assert_eq!(decoded.model.name, MODEL_SMALL_EN);
+        assert_eq!(decoded.model.name, "small.en");
+    }
+
+    #[test]
+    fn model_section_without_verify_key_skips_startup_check() {
+        let decoded = toml::from_str::<ModelConfig>("name = \"base\"\npath = \"~/models/\"\n")
+            .expect("parse model config");
+        assert!(!decoded.verify_on_startup);
+        assert_eq!(decoded.threads, 0);
+    }
+
+    #[test]
+    fn whisper_threads_default_to_available_cores() {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        assert_eq!(whisper_threads(0), cores.min(WHISPER_THREADS_MAX));
+        assert_eq!(whisper_threads(3), 3);
+        assert_eq!(whisper_threads(1000), WHISPER_THREADS_MAX);
+    }
+
+    #[test]
+    fn hallucination_filter_is_on_by_default_and_phrases_can_be_replaced() {
+        let decoded = toml::from_str::<HallucinationConfig>("phrases = [\"Bye now.\"]\n")
+            .expect("parse hallucination config");
+        assert!(decoded.enabled);
+        assert!(decoded.drop_without_speech);
+        assert_eq!(decoded.phrases, ["Bye now."]);
+        assert_eq!(decoded.max_repeats, HALLUCINATION_MAX_REPEATS_DEFAULT);
+        assert!(HallucinationConfig::default()
+            .phrases
+            .iter()
+            .any(|phrase| phrase == "Thank you for watching."));
+    }
+
+    #[test]
+    fn escalation_is_off_by_default_and_probability_is_clamped() {
+        let decoded = toml::from_str::<EscalationConfig>("enabled = true\n")
+            .expect("parse escalation config");
+        assert!(decoded.enabled);
+        assert_eq!(decoded.model, MODEL_LARGE_V3_TURBO);
+        assert_eq!(decoded.min_probability, ESCALATION_MIN_PROBABILITY_DEFAULT);
+        assert_eq!(decoded.cache_mb, ESCALATION_CACHE_MB_DEFAULT);
+        assert!(!AppConfig::default().escalation.enabled);
+        assert_eq!(normalized_escalation_min_probability(1.5), 1.0);
+        assert_eq!(
+            normalized_escalation_min_probability(f32::NAN),
+            ESCALATION_MIN_PROBABILITY_DEFAULT
+        );
+    }
+
+    #[test]
+    fn style_prompt_prefers_profile_then_language_then_global() {
+        let style = toml::from_str::<StyleConfig>(
+            r#"
+profile = "chat"
+prompt = "Global."
+
+[languages]
+pt = "Olá, tudo bem?"
+
+[profiles.chat]
+prompt = "hey sounds good"
+
+[profiles.chat.languages]
+es = "vale, nos vemos"
+"#,
+        )
+        .expect("parse style config");
+        assert_eq!(style.style_prompt("es"), Some("vale, nos vemos"));
+        assert_eq!(style.style_prompt("pt"), Some("hey sounds good"));
+
+        let style = StyleConfig {
+            profile: "missing".to_string(),
+            ..style
+        };
+        assert_eq!(style.style_prompt("pt"), Some("Olá, tudo bem?"));
+        assert_eq!(style.style_prompt("en"), Some("Global."));
+        assert_eq!(StyleConfig::default().style_prompt("en"), None);
+    }
+
+    #[test]
+    fn decoding_presets_apply_overrides_on_top() {
+        assert_eq!(
+            DecodingConfig::default().settings(),
+            DecodingSettings::default()
+        );
+        assert_eq!(decoding_preset(" Fast ").temperature_inc, 0.0);
+        assert_eq!(decoding_preset("accurate").beam_size, 5);
+        assert_eq!(decoding_preset("nonsense"), decoding_preset("balanced"));
+
+        let decoded = toml::from_str::<DecodingConfig>(
+            "preset = \"accurate\"\nbeam_size = 64\nno_speech_thold = 0.4\n",
+        )
+        .expect("parse decoding config");
+        let settings = decoded.settings();
+        assert_eq!(settings.beam_size, BEAM_SIZE_MAX);
+        assert_eq!(settings.no_speech_thold, 0.4);
+        assert_eq!(settings.temperature_inc, 0.2);
+    }
+
+    #[test]
+    fn config_without_audio_section_uses_default_device() {
+        let mut config = AppConfig::default();
+        config.audio.device = "USB Headset".to_string();
+        let encoded = toml::to_string_pretty(&config).expect("serialize config");
+        let legacy = encoded.replace(
+            "[audio]\ndevice = \"USB Headset\"\nresampler = \"sinc\"\nwarm_mic = false\npreroll_ms = 400\n",
+            "",
+        );
+        assert!(!legacy.contains("[audio]"));
+        let decoded = toml::from_str::<AppConfig>(&legacy).expect("parse config");
+        assert_eq!(decoded.audio.device, "");
+        assert_eq!(decoded.audio.resampler, RESAMPLER_SINC);
+
+        let decoded = toml::from_str::<AppConfig>(&encoded).expect("parse config");
+        assert_eq!(decoded.audio.device, "USB Headset");
+    }
+
+    #[test]
+    fn audio_section_without_warm_mic_keys_defaults_to_off() {
+        let decoded = toml::from_str::<AudioConfig>("device = \"\"\nresampler = \"linear\"\n")
+            .expect("parse audio config");
+        assert!(!decoded.warm_mic);
+        assert_eq!(decoded.preroll_ms, PREROLL_MS_DEFAULT);
+        assert_eq!(normalized_preroll_ms(0), PREROLL_MS_MIN);
+        assert_eq!(normalized_preroll_ms(60_000), PREROLL_MS_MAX);
+    }
+
+    #[test]
+    fn general_section_without_auto_stop_uses_default_silence() {
+        let decoded = toml::from_str::<GeneralConfig>(
+            "language = \"en\"\nhotkey = \"ctrl+shift+space\"\nmode = \"auto\"\n",
+        )
+        .expect("parse general config");
+        assert_eq!(decoded.auto_stop_silence_ms, AUTO_STOP_SILENCE_MS_DEFAULT);
+        assert_eq!(decoded.max_recording_secs, MAX_RECORDING_SECS_DEFAULT);
+        assert_eq!(normalized_auto_stop_silence_ms(50), AUTO_STOP_SILENCE_MS_MIN);
+        assert_eq!(normalized_max_recording_secs(0), MAX_RECORDING_SECS_MIN);
+        assert!(!decoded.live_transcription);
+        assert!(!decoded.translate);
+        assert!(decoded.translate_hotkey.is_empty());
+        assert!(!decoded.carry_context);
+        assert_eq!(decoded.carry_context_secs, CARRY_CONTEXT_SECS_DEFAULT);
+        assert_eq!(normalized_carry_context_secs(0), CARRY_CONTEXT_SECS_MIN);
     }
 }
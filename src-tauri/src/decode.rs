@@ -0,0 +1,183 @@
+//! Audio file decoding for `transcribe_file`.
+//!
+//! Decodes WAV/FLAC/MP3/OGG (Vorbis) with symphonia, downmixes to mono, and streams the
+//! result through the same [`StreamResampler`] the recorder uses, so files reach Whisper
+//! as 16 kHz mono exactly like live captures.
+
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::config::RESAMPLER_SINC;
+use crate::dlog;
+use crate::resampler::StreamResampler;
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Decode the first audio track of `path` into 16 kHz mono samples.
+pub fn decode_to_mono_16k(path: &Path) -> Result<Vec<f32>, String> {
+    let file = File::open(path).map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("unsupported audio file {}: {e}", path.display()))?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| format!("no audio track in {}", path.display()))?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("unsupported codec in {}: {e}", path.display()))?;
+
+    let mut interleaved: Option<SampleBuffer<f32>> = None;
+    let mut resampler: Option<StreamResampler> = None;
+    let mut mono = Vec::new();
+    let mut out = Vec::new();
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break;
+            }
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(format!("failed reading {}: {e}", path.display())),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(e)) => {
+                dlog!("decode: skipping corrupt packet in {}: {e}", path.display());
+                continue;
+            }
+            Err(e) => return Err(format!("failed decoding {}: {e}", path.display())),
+        };
+
+        let spec = *decoded.spec();
+        let channels = spec.channels.count();
+        let needed = decoded.capacity() * channels;
+        if interleaved
+            .as_ref()
+            .is_some_and(|buf| buf.capacity() < needed)
+        {
+            interleaved = None;
+        }
+        let buf =
+            interleaved.get_or_insert_with(|| SampleBuffer::new(decoded.capacity() as u64, spec));
+        buf.copy_interleaved_ref(decoded);
+
+        mono.clear();
+        downmix_into(buf.samples(), channels, &mut mono);
+        resampler
+            .get_or_insert_with(|| {
+                StreamResampler::new(spec.rate, TARGET_SAMPLE_RATE, RESAMPLER_SINC)
+            })
+            .process(&mono, &mut out);
+    }
+
+    if let Some(resampler) = resampler.as_mut() {
+        resampler.flush(&mut out);
+    }
+    Ok(out)
+}
+
+/// Average interleaved frames of `channels` channels into mono, appending to `out`.
+fn downmix_into(interleaved: &[f32], channels: usize, out: &mut Vec<f32>) {
+    let channels = channels.max(1);
+    if channels == 1 {
+        out.extend_from_slice(interleaved);
+        return;
+    }
+    let scale = 1.0 / channels as f32;
+    out.extend(
+        interleaved
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() * scale),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Minimal 16-bit PCM WAV writer so the test doesn't need fixtures.
+    fn write_wav(path: &Path, rate: u32, channels: u16, frames: &[Vec<i16>]) {
+        let data_len = (frames.len() * channels as usize * 2) as u32;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&channels.to_le_bytes());
+        bytes.extend_from_slice(&rate.to_le_bytes());
+        bytes.extend_from_slice(&(rate * channels as u32 * 2).to_le_bytes());
+        bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for frame in frames {
+            for sample in frame {
+                bytes.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+        File::create(path).unwrap().write_all(&bytes).unwrap();
+    }
+
+    #[test]
+    fn downmix_averages_channels() {
+        let mut out = Vec::new();
+        downmix_into(&[1.0, 0.0, 0.5, 0.5], 2, &mut out);
+        assert_eq!(out, vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn decodes_stereo_48k_wav_to_16k_mono() {
+        let path = std::env::temp_dir().join(format!("dravis-decode-{}.wav", std::process::id()));
+        // One second: left channel constant, right channel silent.
+        let frames: Vec<Vec<i16>> = (0..48_000).map(|_| vec![16_384, 0]).collect();
+        write_wav(&path, 48_000, 2, &frames);
+
+        let samples = decode_to_mono_16k(&path).expect("decode wav");
+        let _ = std::fs::remove_file(&path);
+
+        assert!(samples.len().abs_diff(16_000) <= 16);
+        // 0.5 full scale on one channel averages to ~0.25 away from the edges.
+        let mid = samples[8_000];
+        assert!((mid - 0.25).abs() < 0.01, "mid sample {mid}");
+    }
+
+    #[test]
+    fn rejects_non_audio_file() {
+        let path = std::env::temp_dir().join(format!("dravis-decode-{}.txt", std::process::id()));
+        File::create(&path)
+            .unwrap()
+            .write_all(b"not audio")
+            .unwrap();
+        let result = decode_to_mono_16k(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(result.is_err());
+    }
+}
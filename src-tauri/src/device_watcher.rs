@@ -0,0 +1,99 @@
+//! Background watcher for audio input hot-plug.
+//!
+//! cpal has no device-change notifications, so a thread polls the input device list.
+//! When the list changes it re-resolves the recorder's device (or fails over mid-recording
+//! if the active device vanished) and emits `audio_device_changed` to the frontend.
+//! A stream error reported by cpal (e.g. `DeviceNotAvailable` on unplug) also triggers
+//! failover, even if the device list looks unchanged. An idle warm-mic stream is moved
+//! the same way so the pre-roll keeps filling from a live device.
+
+use crate::dlog;
+use crate::state::{AppState, AudioDeviceChangedPayload};
+use crate::{audio, set_widget_state};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the input device list is polled. Enumeration is cheap, and 1.5 s is quick
+/// enough that an unplugged headset loses at most a second or two of audio.
+const POLL_INTERVAL: Duration = Duration::from_millis(1500);
+
+pub fn spawn_device_watcher(app: AppHandle) {
+    let spawned = std::thread::Builder::new()
+        .name("audio-device-watcher".to_string())
+        .spawn(move || {
+            let mut known = audio::input_device_names();
+            loop {
+                std::thread::sleep(POLL_INTERVAL);
+                let current = audio::input_device_names();
+                let list_changed = current != known;
+                if let Some(payload) = check_devices(&app, &current, list_changed) {
+                    dlog!(
+                        "audio: input devices changed: {:?} (active: {:?}, failed over: {})",
+                        payload.devices,
+                        payload.active,
+                        payload.failed_over
+                    );
+                    let _ = app.emit("audio_device_changed", payload);
+                }
+                known = current;
+            }
+        });
+
+    if let Err(e) = spawned {
+        dlog!("audio: failed to start device watcher: {e}");
+    }
+}
+
+/// Reconcile the recorder with the current device list. Returns the event payload to emit,
+/// or `None` when nothing changed.
+fn check_devices(
+    app: &AppHandle,
+    devices: &[String],
+    list_changed: bool,
+) -> Option<AudioDeviceChangedPayload> {
+    let state = app.state::<AppState>();
+    let mut inner = state.inner_state.lock().ok()?;
+    let recorder = &mut inner.recorder;
+
+    if recorder.is_recording() {
+        let active_missing = recorder
+            .active_device_name()
+            .map(|name| !devices.contains(&name))
+            .unwrap_or(true);
+        let needs_failover = recorder.stream_failed() || (list_changed && active_missing);
+        if !needs_failover {
+            return None;
+        }
+
+        let failover = recorder.failover();
+        let payload = AudioDeviceChangedPayload {
+            devices: devices.to_vec(),
+            active: recorder.active_device_name(),
+            failed_over: failover.is_ok(),
+        };
+        drop(inner);
+
+        if let Err(err) = failover {
+            dlog!("audio: failover failed: {err}");
+            set_widget_state(app, "error", Some(format!("Microphone lost: {err}")));
+        }
+        return Some(payload);
+    }
+
+    let warm_failed = recorder.warm_mic() && recorder.stream_failed();
+    if !list_changed && !warm_failed {
+        return None;
+    }
+    if recorder.warm_mic() {
+        if let Err(err) = recorder.failover() {
+            dlog!("audio: failed to reopen warm mic stream: {err}");
+        }
+    } else {
+        recorder.refresh_device();
+    }
+    Some(AudioDeviceChangedPayload {
+        devices: devices.to_vec(),
+        active: recorder.active_device_name(),
+        failed_over: false,
+    })
+}
@@ -0,0 +1,394 @@
+//! Model downloads.
+//!
+//! A model is downloaded to `<file>.part` next to its final path and renamed into place
+//! only once complete, so an interrupted download never looks like an installed model.
+//! Failed transfers are retried with an HTTP `Range` request that resumes from the end of
+//! the `.part` file; a cancelled download keeps it, so the next download resumes too.
+//!
+//! A finished download is checked against the catalog's SHA-256, or the one Hugging Face
+//! reports for the file, before it's moved into place. Its hash is then recorded next to
+//! the model for later checks (see `verify`).
+
+use crate::dlog;
+use crate::state::ModelDownloadProgressPayload;
+use crate::verify;
+use reqwest::header::{HeaderName, CONTENT_RANGE, ETAG, RANGE};
+use reqwest::StatusCode;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Attempts per download before giving up; each one resumes where the last stopped.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Wait before retry `n` is `n` times this.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Minimum time between two `model_download_progress` events.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Free space to leave on the disk besides the model itself.
+const DISK_MARGIN_BYTES: u64 = 100 * 1024 * 1024;
+
+enum AttemptError {
+    /// Worth retrying (network error, server error, truncated body).
+    Retry(String),
+    Fatal(String),
+    Cancelled,
+}
+
+/// Where the download of `model_path` is written until it completes.
+pub fn part_path(model_path: &Path) -> PathBuf {
+    let mut name = model_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    model_path.with_file_name(name)
+}
+
+/// Download `url` to `model_path`, resuming a previous `.part` file. Setting `cancel` stops
+/// the download with an error and keeps the partial file. `expected_sha256` is the
+/// catalog's hash, if it has one.
+pub async fn run_model_download(
+    app: AppHandle,
+    model_path: PathBuf,
+    url: String,
+    expected_sha256: Option<String>,
+    cancel: Arc<AtomicBool>,
+) -> Result<(), String> {
+    if let Some(parent) = model_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("failed to create model directory {}: {e}", parent.display()))?;
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        download(&app, &url, &model_path, expected_sha256, &cancel)
+    })
+    .await
+    .map_err(|e| format!("download task failed: {e}"))?
+}
+
+fn download(
+    app: &AppHandle,
+    url: &str,
+    model_path: &Path,
+    expected_sha256: Option<String>,
+    cancel: &AtomicBool,
+) -> Result<(), String> {
+    let part = part_path(model_path);
+    let client = reqwest::blocking::Client::new();
+    let mut progress = Progress::new(app);
+
+    let mut attempt = 1;
+    loop {
+        match download_attempt(&client, url, &part, cancel, &mut progress) {
+            Ok(()) => break,
+            Err(AttemptError::Cancelled) => {
+                dlog!("download: cancelled, keeping {}", part.display());
+                return Err(
+                    "model download cancelled; downloading again resumes where it stopped"
+                        .to_string(),
+                );
+            }
+            Err(AttemptError::Fatal(err)) => return Err(err),
+            Err(AttemptError::Retry(err)) => {
+                if attempt >= MAX_ATTEMPTS {
+                    return Err(format!("{err} (gave up after {attempt} attempts)"));
+                }
+                dlog!("download: attempt {attempt} failed, resuming: {err}");
+                let retry_at = Instant::now() + RETRY_DELAY * attempt;
+                while Instant::now() < retry_at {
+                    if cancel.load(Ordering::SeqCst) {
+                        return Err("model download cancelled".to_string());
+                    }
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                attempt += 1;
+            }
+        }
+    }
+
+    let expected = expected_sha256.or_else(|| server_sha256(url));
+    let actual = verify::sha256_file(&part)?;
+    match &expected {
+        Some(expected) if !actual.eq_ignore_ascii_case(expected) => {
+            let _ = fs::remove_file(&part);
+            return Err(format!(
+                "downloaded model is corrupted (SHA-256 {actual}, expected {expected}); \
+                 it was deleted, please download again"
+            ));
+        }
+        Some(_) => dlog!("download: SHA-256 verified"),
+        None => dlog!("download: no SHA-256 to verify against, recording {actual}"),
+    }
+    verify::record_hash(model_path, &actual)?;
+
+    fs::rename(&part, model_path).map_err(|e| {
+        format!(
+            "failed moving {} to {}: {e}",
+            part.display(),
+            model_path.display()
+        )
+    })?;
+    progress.finish();
+    dlog!("download: saved {}", model_path.display());
+    Ok(())
+}
+
+fn download_attempt(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    part: &Path,
+    cancel: &AtomicBool,
+    progress: &mut Progress,
+) -> Result<(), AttemptError> {
+    let offset = fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+    let mut request = client.get(url);
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={offset}-"));
+    }
+    let mut response = request
+        .send()
+        .map_err(|e| AttemptError::Retry(format!("model download request failed: {e}")))?;
+
+    let status = response.status();
+    let content_range_total = response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(content_range_total);
+
+    if status == StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+        if content_range_total == Some(offset) {
+            // The previous attempt got every byte but didn't get to rename.
+            return Ok(());
+        }
+        let _ = fs::remove_file(part);
+        return Err(AttemptError::Retry(
+            "partial download doesn't match the server file; starting over".to_string(),
+        ));
+    }
+    if !status.is_success() {
+        let err = format!("model download failed with status {status}");
+        return Err(if status.is_server_error() {
+            AttemptError::Retry(err)
+        } else {
+            AttemptError::Fatal(err)
+        });
+    }
+
+    // A plain 200 means the server ignored the range; start over.
+    let resumed = status == StatusCode::PARTIAL_CONTENT;
+    let mut downloaded = if resumed { offset } else { 0 };
+    let total = if resumed {
+        content_range_total.or_else(|| response.content_length().map(|len| offset + len))
+    } else {
+        response.content_length()
+    }
+    .unwrap_or(0);
+
+    if let Some(dir) = part.parent() {
+        check_disk_space(dir, total.saturating_sub(downloaded)).map_err(AttemptError::Fatal)?;
+    }
+
+    let opened = if resumed {
+        dlog!("download: resuming at {offset} of {total} bytes");
+        OpenOptions::new().append(true).open(part)
+    } else {
+        File::create(part)
+    };
+    let mut file = opened
+        .map_err(|e| AttemptError::Fatal(format!("failed creating {}: {e}", part.display())))?;
+
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        if cancel.load(Ordering::SeqCst) {
+            return Err(AttemptError::Cancelled);
+        }
+        let read = response
+            .read(&mut buf)
+            .map_err(|e| AttemptError::Retry(format!("failed reading model stream: {e}")))?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buf[..read])
+            .map_err(|e| AttemptError::Fatal(format!("failed writing model file: {e}")))?;
+        downloaded += read as u64;
+        progress.update(read as u64, downloaded, total);
+    }
+
+    file.sync_all()
+        .map_err(|e| AttemptError::Fatal(format!("failed writing model file: {e}")))?;
+    if total > 0 && downloaded < total {
+        return Err(AttemptError::Retry(format!(
+            "download ended early at {downloaded} of {total} bytes"
+        )));
+    }
+    Ok(())
+}
+
+/// SHA-256 the server reports for `url`. Hugging Face sends it as `X-Linked-Etag` on the
+/// redirect to its file storage, so redirects aren't followed.
+pub fn server_sha256(url: &str) -> Option<String> {
+    let client = reqwest::blocking::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(15))
+        .build()
+        .ok()?;
+    let response = client.head(url).send().ok()?;
+    let headers = response.headers();
+    [HeaderName::from_static("x-linked-etag"), ETAG]
+        .iter()
+        .filter_map(|name| headers.get(name)?.to_str().ok())
+        .map(|etag| {
+            etag.trim_start_matches("W/")
+                .trim_matches('"')
+                .to_lowercase()
+        })
+        .find(|etag| verify::is_sha256_hex(etag))
+}
+
+/// Total size from a `Content-Range` header: `bytes 100-199/1000` or `bytes */1000`.
+fn content_range_total(value: &str) -> Option<u64> {
+    value.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+fn check_disk_space(dir: &Path, needed: u64) -> Result<(), String> {
+    let Some(available) = available_space(dir) else {
+        return Ok(());
+    };
+    if available < needed + DISK_MARGIN_BYTES {
+        return Err(format!(
+            "not enough disk space for the model: {} MB needed, {} MB free in {}",
+            (needed + DISK_MARGIN_BYTES) / 1_000_000,
+            available / 1_000_000,
+            dir.display()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn available_space(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    // SAFETY: `path` is a valid C string and `stat` is a plain struct statvfs fills in.
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // The field types differ between platforms (u32 block counts on macOS).
+    #[allow(clippy::useless_conversion)]
+    Some(u64::from(stat.f_bavail) * u64::from(stat.f_frsize))
+}
+
+#[cfg(not(unix))]
+fn available_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+/// Emits `model_download_progress` with speed and ETA, at most every [`PROGRESS_INTERVAL`].
+struct Progress<'a> {
+    app: &'a AppHandle,
+    started: Instant,
+    /// Bytes received by this download (not counting what a `.part` file already had).
+    received: u64,
+    last_emit: Option<Instant>,
+    last: ModelDownloadProgressPayload,
+}
+
+impl<'a> Progress<'a> {
+    fn new(app: &'a AppHandle) -> Self {
+        Self {
+            app,
+            started: Instant::now(),
+            received: 0,
+            last_emit: None,
+            last: ModelDownloadProgressPayload::default(),
+        }
+    }
+
+    fn update(&mut self, read: u64, downloaded: u64, total: u64) {
+        self.received += read;
+        if self
+            .last_emit
+            .is_some_and(|at| at.elapsed() < PROGRESS_INTERVAL)
+        {
+            return;
+        }
+        self.last_emit = Some(Instant::now());
+        self.last = progress_payload(downloaded, total, self.received, self.started.elapsed());
+        let _ = self.app.emit("model_download_progress", &self.last);
+    }
+
+    fn finish(&mut self) {
+        self.last.progress = 1.0;
+        self.last.downloaded_bytes = self.last.total_bytes.max(self.last.downloaded_bytes);
+        self.last.eta_secs = Some(0.0);
+        let _ = self.app.emit("model_download_progress", &self.last);
+    }
+}
+
+fn progress_payload(
+    downloaded: u64,
+    total: u64,
+    received: u64,
+    elapsed: Duration,
+) -> ModelDownloadProgressPayload {
+    let secs = elapsed.as_secs_f64();
+    let bytes_per_sec = if secs > 0.0 {
+        received as f64 / secs
+    } else {
+        0.0
+    };
+    let eta_secs = (total > 0 && bytes_per_sec > 0.0)
+        .then(|| total.saturating_sub(downloaded) as f64 / bytes_per_sec);
+    let progress = if total > 0 {
+        (downloaded as f64 / total as f64).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    ModelDownloadProgressPayload {
+        progress,
+        downloaded_bytes: downloaded,
+        total_bytes: total,
+        bytes_per_sec,
+        eta_secs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part_file_sits_next_to_the_model() {
+        assert_eq!(
+            part_path(Path::new("/models/ggml-base.en.bin")),
+            PathBuf::from("/models/ggml-base.en.bin.part")
+        );
+    }
+
+    #[test]
+    fn reads_total_from_content_range() {
+        assert_eq!(content_range_total("bytes 100-199/1000"), Some(1000));
+        assert_eq!(content_range_total("bytes */1000"), Some(1000));
+        assert_eq!(content_range_total("bytes 0-99/*"), None);
+    }
+
+    #[test]
+    fn speed_and_eta_ignore_resumed_bytes() {
+        // Resumed at 600 of 1000 bytes; 200 received in 2 s.
+        let payload = progress_payload(800, 1000, 200, Duration::from_secs(2));
+        assert_eq!(payload.bytes_per_sec, 100.0);
+        assert_eq!(payload.eta_secs, Some(2.0));
+        assert_eq!(payload.progress, 0.8);
+
+        let unknown = progress_payload(500, 0, 500, Duration::from_secs(1));
+        assert_eq!(unknown.eta_secs, None);
+        assert_eq!(unknown.progress, 0.0);
+    }
+}
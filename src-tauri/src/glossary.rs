@@ -0,0 +1,159 @@
+//! Glossary ranking for the Whisper initial prompt.
+//!
+//! The prompt only has room for ~850 characters, so with a long dictionary some terms
+//! don't fit. Terms are ordered so the ones that matter come first: pinned terms in their
+//! own order, then (with `ranking = "usage"`) the rest by how often they appeared in past
+//! dictations. Ties, and everything with `ranking = "config"`, keep the dictionary order.
+//!
+//! Usage counts live in `~/.dravis-flow/glossary_usage.json` and are updated after each
+//! dictation.
+
+use crate::config::{
+    config_dir, normalized_glossary_ranking, DictionaryConfig, GLOSSARY_RANKING_USAGE,
+};
+use crate::dlog;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+/// How often each dictionary term appeared in dictations, keyed by lowercase term.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GlossaryUsage {
+    #[serde(default)]
+    pub counts: HashMap<String, u32>,
+}
+
+fn usage_path() -> PathBuf {
+    config_dir().join("glossary_usage.json")
+}
+
+impl GlossaryUsage {
+    /// Counts saved by earlier runs; empty when there are none or the file is unreadable.
+    pub fn load() -> Self {
+        let Ok(text) = fs::read_to_string(usage_path()) else {
+            return Self::default();
+        };
+        serde_json::from_str(&text).unwrap_or_else(|e| {
+            dlog!("glossary: ignoring unreadable usage file: {e}");
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = usage_path();
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("failed serializing glossary usage: {e}"))?;
+        fs::write(&path, text).map_err(|e| format!("failed writing {}: {e}", path.display()))
+    }
+
+    pub fn count(&self, term: &str) -> u32 {
+        self.counts
+            .get(&term.trim().to_lowercase())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Count each of `terms` that occurs in `text` as a whole word or phrase. Returns
+    /// whether any count changed.
+    pub fn record(&mut self, text: &str, terms: &[String]) -> bool {
+        let text = text.to_lowercase();
+        let mut changed = false;
+        for term in terms {
+            let term = term.trim().to_lowercase();
+            let hits = count_occurrences(&text, &term);
+            if hits > 0 {
+                *self.counts.entry(term).or_insert(0) += hits;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Occurrences of `term` in `text` that aren't part of a longer word.
+fn count_occurrences(text: &str, term: &str) -> u32 {
+    if term.is_empty() {
+        return 0;
+    }
+    let is_word_char = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    text.match_indices(term)
+        .filter(|&(at, _)| {
+            let before = text[..at].chars().next_back();
+            let after = text[at + term.len()..].chars().next();
+            !is_word_char(before) && !is_word_char(after)
+        })
+        .count() as u32
+}
+
+/// Glossary terms, most important first: pinned terms, then the dictionary words ranked
+/// per `dictionary.ranking`. Blank and repeated (case-insensitively) terms are left out.
+pub fn ranked_terms(dictionary: &DictionaryConfig, usage: &GlossaryUsage) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut unique = |terms: &[String]| -> Vec<String> {
+        terms
+            .iter()
+            .map(|term| term.trim())
+            .filter(|term| !term.is_empty() && seen.insert(term.to_lowercase()))
+            .map(str::to_string)
+            .collect()
+    };
+    let mut ranked = unique(&dictionary.pinned);
+    let mut words = unique(&dictionary.words);
+    if normalized_glossary_ranking(&dictionary.ranking) == GLOSSARY_RANKING_USAGE {
+        // Stable, so equally used terms keep the dictionary order.
+        words.sort_by_key(|word| std::cmp::Reverse(usage.count(word)));
+    }
+    ranked.extend(words);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GLOSSARY_RANKING_CONFIG;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn records_whole_word_and_phrase_matches() {
+        let mut usage = GlossaryUsage::default();
+        let terms = strings(&["Rust", "npm install", "Go"]);
+        assert!(usage.record(
+            "Ran npm install, then Rust; rustc and Rusty don't count.",
+            &terms
+        ));
+        assert!(usage.record("rust again. Good to go", &terms));
+        assert_eq!(usage.count("rust"), 2);
+        assert_eq!(usage.count("NPM install"), 1);
+        assert_eq!(usage.count("Go"), 1);
+        assert!(!usage.record("nothing here", &terms));
+    }
+
+    #[test]
+    fn pinned_terms_lead_and_used_terms_follow() {
+        let mut usage = GlossaryUsage::default();
+        usage.record(
+            "Kubernetes and Docker, Docker again",
+            &strings(&["Docker", "Kubernetes"]),
+        );
+        let mut dictionary = DictionaryConfig {
+            words: strings(&["Bun", "Kubernetes", "Tauri", "Docker", " "]),
+            pinned: strings(&["Tauri", "DraVis"]),
+            ranking: GLOSSARY_RANKING_USAGE.to_string(),
+            ..DictionaryConfig::default()
+        };
+        assert_eq!(
+            ranked_terms(&dictionary, &usage),
+            ["Tauri", "DraVis", "Docker", "Kubernetes", "Bun"]
+        );
+
+        dictionary.ranking = GLOSSARY_RANKING_CONFIG.to_string();
+        assert_eq!(
+            ranked_terms(&dictionary, &usage),
+            ["Tauri", "DraVis", "Bun", "Kubernetes", "Docker"]
+        );
+    }
+}
@@ -0,0 +1,398 @@
+//! Hallucination filter for Whisper output.
+//!
+//! On near-silent or noisy audio Whisper tends to make text up: closing lines it learned
+//! from video subtitles ("Thank you for watching."), tags for non-speech such as
+//! "[BLANK_AUDIO]" or "(music)", or one segment looped over and over. The segments of each
+//! transcribed buffer pass through [`filter_segments`] before they're joined, so made-up
+//! text neither reaches the formatter nor conditions the next chunk's prompt.
+//!
+//! Each segment is dropped when the VAD heard no speech under it. For recordings that's the
+//! recorder's VAD, whose noise floor has adapted to the room over the whole recording; only
+//! audio that never went through it (files) gets a VAD pass of its own, see
+//! [`detect_speech`]. Otherwise `[...]` tags are cut from it, a segment that is only a
+//! `(...)`, `*...*` or `♪` tag is dropped, and sentences matching `phrases` are cut. Runs
+//! of the same sentence, and then of the same segment, longer than `max_repeats` collapse
+//! to their first. Everything removed is logged.
+
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::chunking::normalize_word;
+use crate::config::HallucinationConfig;
+use crate::dlog;
+use crate::vad::{SpeechSegment, VoiceActivityDetector};
+use crate::whisper::Segment;
+use std::collections::HashSet;
+
+/// Speech this close to a segment still counts for it; Whisper's segment times can be off
+/// by a few hundred ms.
+const SPEECH_TOLERANCE_MS: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reason {
+    NoSpeech,
+    Tag,
+    Phrase,
+    Repeat,
+}
+
+impl Reason {
+    fn label(self) -> &'static str {
+        match self {
+            Reason::NoSpeech => "text without speech",
+            Reason::Tag => "non-speech tag",
+            Reason::Phrase => "known hallucination",
+            Reason::Repeat => "repetition loop",
+        }
+    }
+}
+
+/// Text taken out of Whisper's output, and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Removal {
+    pub reason: Reason,
+    pub text: String,
+}
+
+/// Filter transcribed segments. `speech` is where the VAD heard speech, as sample indices
+/// in the segment times' timeline. Logs every removal.
+pub fn filter_segments(
+    segments: Vec<Segment>,
+    speech: &[SpeechSegment],
+    config: &HallucinationConfig,
+) -> Vec<Segment> {
+    if !config.enabled || segments.is_empty() {
+        return segments;
+    }
+    let speech = config.drop_without_speech.then(|| speech_ranges_ms(speech));
+    let (kept, removed) = filter(segments, speech.as_deref(), config);
+    for removal in &removed {
+        dlog!(
+            "hallucination: removed {}: {:?}",
+            removal.reason.label(),
+            removal.text
+        );
+    }
+    kept
+}
+
+/// Speech in 16 kHz `audio` the recorder's VAD hasn't seen, such as a decoded file. Empty
+/// when the filter won't look at it.
+pub fn detect_speech(audio: &[f32], config: &HallucinationConfig) -> Vec<SpeechSegment> {
+    if !config.enabled || !config.drop_without_speech {
+        return Vec::new();
+    }
+    let mut vad = VoiceActivityDetector::new(TARGET_SAMPLE_RATE);
+    vad.process(audio);
+    vad.finish()
+}
+
+/// `speech` as `[start, end)` ranges in ms.
+fn speech_ranges_ms(speech: &[SpeechSegment]) -> Vec<(u64, u64)> {
+    let to_ms = |sample: usize| sample as u64 * 1000 / TARGET_SAMPLE_RATE as u64;
+    speech
+        .iter()
+        .map(|segment| (to_ms(segment.start), to_ms(segment.end)))
+        .collect()
+}
+
+/// The segments to keep and what was removed. Without `speech` ranges nothing is dropped
+/// for lack of speech.
+fn filter(
+    segments: Vec<Segment>,
+    speech: Option<&[(u64, u64)]>,
+    config: &HallucinationConfig,
+) -> (Vec<Segment>, Vec<Removal>) {
+    let phrases: HashSet<String> = config
+        .phrases
+        .iter()
+        .map(|phrase| normalize(phrase))
+        .filter(|phrase| !phrase.is_empty())
+        .collect();
+    let max_repeats = config.max_repeats.max(1) as usize;
+
+    let mut removed = Vec::new();
+    let mut kept = Vec::new();
+    for mut segment in segments {
+        if speech.is_some_and(|speech| !has_speech(&segment, speech)) {
+            removed.push(Removal {
+                reason: Reason::NoSpeech,
+                text: segment.text,
+            });
+            continue;
+        }
+        segment.text = clean_text(&segment.text, &phrases, max_repeats, &mut removed);
+        if !segment.text.is_empty() {
+            kept.push(segment);
+        }
+    }
+
+    let (kept, loops) = collapse_loops(kept, max_repeats, |segment| normalize(&segment.text));
+    removed.extend(loops.into_iter().map(|segment| Removal {
+        reason: Reason::Repeat,
+        text: segment.text,
+    }));
+    (kept, removed)
+}
+
+fn has_speech(segment: &Segment, speech: &[(u64, u64)]) -> bool {
+    speech.iter().any(|&(start, end)| {
+        start < segment.end_ms + SPEECH_TOLERANCE_MS && end + SPEECH_TOLERANCE_MS > segment.start_ms
+    })
+}
+
+/// `text` without tags, known phrases and looped sentences; empty when nothing is left.
+fn clean_text(
+    text: &str,
+    phrases: &HashSet<String>,
+    max_repeats: usize,
+    removed: &mut Vec<Removal>,
+) -> String {
+    let (text, tags) = strip_bracket_tags(text);
+    removed.extend(tags.into_iter().map(|text| Removal {
+        reason: Reason::Tag,
+        text,
+    }));
+    let text = text.trim();
+    if is_tag(text) {
+        removed.push(Removal {
+            reason: Reason::Tag,
+            text: text.to_string(),
+        });
+        return String::new();
+    }
+
+    let mut kept = Vec::new();
+    for sentence in sentences(text) {
+        if phrases.contains(&normalize(sentence)) {
+            removed.push(Removal {
+                reason: Reason::Phrase,
+                text: sentence.trim().to_string(),
+            });
+        } else {
+            kept.push(sentence);
+        }
+    }
+    let (kept, loops) = collapse_loops(kept, max_repeats, |sentence| normalize(sentence));
+    removed.extend(loops.into_iter().map(|sentence| Removal {
+        reason: Reason::Repeat,
+        text: sentence.trim().to_string(),
+    }));
+    kept.concat()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `text` without its `[...]` tags (`[BLANK_AUDIO]`, `[Music]`), and the tags.
+fn strip_bracket_tags(text: &str) -> (String, Vec<String>) {
+    let mut out = String::new();
+    let mut tags = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find(']').map(|close| open + close + 1) else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        tags.push(rest[open..close].to_string());
+        rest = &rest[close..];
+    }
+    out.push_str(rest);
+    (out, tags)
+}
+
+/// A whole segment that only describes sound: `(music)`, `*laughs*`, `♪ ... ♪`.
+fn is_tag(text: &str) -> bool {
+    let wrapped =
+        |open: char, close: char| text.len() > 1 && text.starts_with(open) && text.ends_with(close);
+    wrapped('(', ')') || wrapped('*', '*') || text.starts_with('♪') || text.ends_with('♪')
+}
+
+/// Sentences of `text`, each with the whitespace before it, so they concatenate back to
+/// `text`. A `.`, `!` or `?` only ends a sentence before whitespace ("Amara.org", "3.5").
+fn sentences(text: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next_is_space = chars.peek().is_none_or(|&(_, next)| next.is_whitespace());
+        let ends =
+            matches!(c, '。' | '！' | '？') || (matches!(c, '.' | '!' | '?') && next_is_space);
+        if ends {
+            let end = i + c.len_utf8();
+            out.push(&text[start..end]);
+            start = end;
+        }
+    }
+    if start < text.len() {
+        out.push(&text[start..]);
+    }
+    out
+}
+
+/// Collapse every run of more than `max_repeats` consecutive items with the same key to
+/// its first item. Returns the kept and the removed items; empty keys never form a run.
+fn collapse_loops<T>(
+    items: Vec<T>,
+    max_repeats: usize,
+    key: impl Fn(&T) -> String,
+) -> (Vec<T>, Vec<T>) {
+    let mut runs: Vec<(String, Vec<T>)> = Vec::new();
+    for item in items {
+        let item_key = key(&item);
+        match runs.last_mut() {
+            Some((run_key, run)) if !item_key.is_empty() && *run_key == item_key => run.push(item),
+            _ => runs.push((item_key, vec![item])),
+        }
+    }
+
+    let mut kept = Vec::new();
+    let mut removed = Vec::new();
+    for (_, mut run) in runs {
+        if run.len() > max_repeats {
+            removed.extend(run.drain(1..));
+        }
+        kept.extend(run);
+    }
+    (kept, removed)
+}
+
+/// Lowercase words without punctuation, for comparing text.
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(normalize_word)
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vad::{extract_voiced, voiced_segments};
+
+    fn segment(text: &str, start_ms: u64, end_ms: u64) -> Segment {
+        Segment {
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            tokens: Vec::new(),
+            probability: 0.9,
+        }
+    }
+
+    fn texts(segments: &[Segment]) -> Vec<&str> {
+        segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn cuts_tags_phrases_and_looped_sentences() {
+        let config = HallucinationConfig::default();
+        let segments = vec![
+            segment("[BLANK_AUDIO]", 0, 1000),
+            segment("Ship it on Amara.org by 3.5 [Music] today.", 1000, 3000),
+            segment("(upbeat music)", 3000, 4000),
+            segment("Go. Go. I said go. I said go. I said go.", 4000, 6000),
+            segment("All done. Thank you for watching!", 6000, 8000),
+        ];
+        let (kept, removed) = filter(segments, None, &config);
+        assert_eq!(
+            texts(&kept),
+            [
+                "Ship it on Amara.org by 3.5 today.",
+                "Go. Go. I said go.",
+                "All done."
+            ]
+        );
+        let reasons: Vec<Reason> = removed.iter().map(|r| r.reason).collect();
+        assert_eq!(
+            reasons,
+            [
+                Reason::Tag,
+                Reason::Tag,
+                Reason::Tag,
+                Reason::Repeat,
+                Reason::Repeat,
+                Reason::Phrase
+            ]
+        );
+        assert_eq!(removed[5].text, "Thank you for watching!");
+    }
+
+    #[test]
+    fn collapses_looped_segments_but_keeps_short_repeats() {
+        let config = HallucinationConfig::default();
+        let segments = vec![
+            segment("No, no.", 0, 500),
+            segment("No, no.", 500, 1000),
+            segment("I'll fix it.", 1000, 2000),
+            segment("I'll fix it", 2000, 3000),
+            segment("i'll fix it!", 3000, 4000),
+        ];
+        let (kept, removed) = filter(segments, None, &config);
+        assert_eq!(texts(&kept), ["No, no.", "No, no.", "I'll fix it."]);
+        assert_eq!(removed.len(), 2);
+        assert!(removed.iter().all(|r| r.reason == Reason::Repeat));
+    }
+
+    #[test]
+    fn drops_segments_without_speech_under_them() {
+        let config = HallucinationConfig::default();
+        let speech = [(1000, 2500)];
+        let segments = vec![
+            segment("Real words.", 900, 2600),
+            segment("Thank you.", 5000, 6000),
+        ];
+        let (kept, removed) = filter(segments, Some(&speech), &config);
+        assert_eq!(texts(&kept), ["Real words."]);
+        assert_eq!(removed[0].reason, Reason::NoSpeech);
+
+        // Silence: nothing the VAD calls speech, so everything goes.
+        let silence = vec![0.0; TARGET_SAMPLE_RATE as usize];
+        let speech = detect_speech(&silence, &config);
+        let kept = filter_segments(vec![segment("You", 0, 1000)], &speech, &config);
+        assert!(kept.is_empty());
+        let disabled = HallucinationConfig {
+            enabled: false,
+            ..config
+        };
+        let kept = filter_segments(vec![segment("You", 0, 1000)], &speech, &disabled);
+        assert_eq!(texts(&kept), ["You"]);
+    }
+
+    #[test]
+    fn keeps_quiet_speech_the_recorder_heard() {
+        // A quiet speaker in a quiet room: the recorder's floor adapted to the room during
+        // the leading silence, so its VAD keeps the speech that extract_voiced hands over.
+        let config = HallucinationConfig::default();
+        let rate = TARGET_SAMPLE_RATE as usize;
+        let quiet_speech = |len: usize| -> Vec<f32> {
+            (0..len)
+                .map(|i| {
+                    (i as f32 * 2.0 * std::f32::consts::PI * 220.0 / rate as f32).sin() * 0.012
+                })
+                .collect()
+        };
+        let room = |len: usize| -> Vec<f32> {
+            (0..len)
+                .map(|i| if i % 2 == 0 { 0.0004 } else { -0.0004 })
+                .collect()
+        };
+        let mut recording = room(2 * rate);
+        recording.extend(quiet_speech(2 * rate));
+        recording.extend(room(rate));
+
+        let mut vad = VoiceActivityDetector::new(TARGET_SAMPLE_RATE);
+        vad.process(&recording);
+        let segments = vad.finish();
+        assert!(!segments.is_empty());
+        let voiced = extract_voiced(&recording, &segments, TARGET_SAMPLE_RATE);
+        let speech = voiced_segments(recording.len(), &segments, TARGET_SAMPLE_RATE);
+
+        let duration_ms = voiced.len() as u64 * 1000 / rate as u64;
+        let kept = filter_segments(
+            vec![segment("Ship the migration today.", 0, duration_ms)],
+            &speech,
+            &config,
+        );
+        assert_eq!(texts(&kept), ["Ship the migration today."]);
+    }
+}
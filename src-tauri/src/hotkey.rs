@@ -27,6 +27,7 @@ pub enum ShortcutAction {
 /// - `toggle_shortcut_held` — whether the key is currently considered physically held
 /// - `toggle_active` — whether we are in toggle mode (recording, waiting for second tap)
 /// - `held_ms` — elapsed milliseconds since the key was pressed (`None` = unknown, treated as long hold)
+/// - `auto_mode` — `auto` recording mode: releasing never stops, silence does (or a second tap)
 pub fn resolve_shortcut_action(
     pressed: bool,
     is_idle: bool,
@@ -34,6 +35,7 @@ pub fn resolve_shortcut_action(
     toggle_shortcut_held: bool,
     toggle_active: bool,
     held_ms: Option<u128>,
+    auto_mode: bool,
 ) -> Option<ShortcutAction> {
     if pressed {
         // Ignore OS key-repeat events while the key is physically held.
@@ -51,6 +53,10 @@ pub fn resolve_shortcut_action(
     } else {
         // Released
         if is_recording && !toggle_active {
+            if auto_mode {
+                // Hands-free: keep recording however long the key was held.
+                return Some(ShortcutAction::ToggleActivated);
+            }
             let ms = held_ms.unwrap_or(HOLD_THRESHOLD_MS + 1);
             if ms >= HOLD_THRESHOLD_MS {
                 // Held long enough: push-to-talk stop.
@@ -103,6 +109,7 @@ mod tests {
             false, // toggle_shortcut_held (already cleared by caller on release)
             false, // toggle_active
             Some(150), // held_ms < HOLD_THRESHOLD_MS
+            false,     // auto_mode
         );
         assert_eq!(action, Some(ShortcutAction::ToggleActivated));
     }
@@ -117,6 +124,7 @@ mod tests {
             false,
             false,
             Some(350), // held_ms >= HOLD_THRESHOLD_MS
+            false,
         );
         assert_eq!(action, Some(ShortcutAction::Stop));
     }
@@ -131,6 +139,7 @@ mod tests {
             true,  // toggle_shortcut_held = true
             false,
             None,
+            false,
         );
         assert_eq!(action, None);
     }
@@ -145,10 +154,26 @@ mod tests {
             false, // toggle_shortcut_held = false (fresh press)
             true,  // toggle_active
             None,
+            false,
         );
         assert_eq!(action, Some(ShortcutAction::Stop));
     }
 
+    #[test]
+    fn long_hold_in_auto_mode_keeps_recording() {
+        // auto mode: releasing after a long hold hands over to the silence auto-stop
+        let action = resolve_shortcut_action(
+            false,
+            false,
+            true,
+            false,
+            false,
+            Some(2_000),
+            true, // auto_mode
+        );
+        assert_eq!(action, Some(ShortcutAction::ToggleActivated));
+    }
+
     // ── config_combo_to_shortcut tests ───────────────────────────────────────
 
     #[test]
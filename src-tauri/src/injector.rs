@@ -231,6 +231,16 @@ pub fn paste_text(text: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Put text on the clipboard without pasting it anywhere.
+pub fn copy_text(text: &str) -> Result<(), String> {
+    let mut clipboard = Clipboard::new().map_err(|e| format!("clipboard init failed: {e}"))?;
+    clipboard
+        .set_text(text.to_string())
+        .map_err(|e| format!("clipboard set failed: {e}"))?;
+    dlog_msg(&format!("injector: copied {} chars to clipboard", text.len()));
+    Ok(())
+}
+
 /// Fallback: use osascript to send Cmd+V.
 #[cfg(target_os = "macos")]
 fn paste_cmd_v_osascript() -> Result<(), String> {
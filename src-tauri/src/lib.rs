@@ -1,13 +1,30 @@
 mod app_setup;
 mod audio;
+mod auto_stop;
+mod benchmark;
+mod carryover;
+mod chunking;
 mod commands;
 mod config;
+mod decode;
+mod device_watcher;
+mod download;
 mod formatter;
+mod glossary;
+mod hallucination;
 mod hotkey;
 mod injector;
+mod live;
+mod model_cache;
+mod models;
 mod pipeline;
 mod prompt;
+mod resampler;
+mod ring;
 mod state;
+mod vad;
+mod verify;
+mod watchdog;
 mod whisper;
 
 // Re-export commands so tauri::generate_handler! can find their __cmd__* macros at crate root.
@@ -21,8 +38,8 @@ use std::io::{BufWriter, Write};
 use std::sync::atomic::Ordering;
 use std::sync::{Mutex, OnceLock};
 use tauri::{AppHandle, Emitter, Manager};
-use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
-use whisper::WhisperEngine;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use whisper::{DetectedLanguage, WhisperEngine};
 
 // ── File-based logging ──────────────────────────────────────────────────
 
@@ -64,11 +81,13 @@ macro_rules! dlog {
 
 const MODE_HOLD: &str = "hold";
 const MODE_TOGGLE: &str = "toggle";
+/// Tap to start; recording stops by itself after trailing silence (see `auto_stop`).
+pub(crate) const MODE_AUTO: &str = "auto";
 
 pub(crate) fn normalize_recording_mode(mode: &str) -> Option<String> {
     let normalized = mode.trim().to_lowercase();
     match normalized.as_str() {
-        MODE_HOLD | MODE_TOGGLE => Some(normalized),
+        MODE_HOLD | MODE_TOGGLE | MODE_AUTO => Some(normalized),
         _ => None,
     }
 }
@@ -78,9 +97,21 @@ pub(crate) fn sanitize_recording_mode(mode: &str) -> String {
 }
 
 pub(crate) fn set_widget_state(app: &AppHandle, status: &str, message: Option<String>) {
+    set_widget_state_with_language(app, status, message, None);
+}
+
+/// [`set_widget_state`] for the steps after a transcription with `language = "auto"`,
+/// so listeners learn which language was detected.
+pub(crate) fn set_widget_state_with_language(
+    app: &AppHandle,
+    status: &str,
+    message: Option<String>,
+    detected_language: Option<DetectedLanguage>,
+) {
     let payload = StatusPayload {
         status: status.to_string(),
         message,
+        detected_language,
     };
 
     if let Some(widget) = app.get_webview_window("widget") {
@@ -101,11 +132,22 @@ pub(crate) fn set_widget_state(app: &AppHandle, status: &str, message: Option<St
 
 // ── Shortcut event handler ──────────────────────────────────────────────
 
-fn handle_shortcut_event(app: &AppHandle, state: ShortcutState) {
+/// Parse a config combo like `"ctrl+shift+space"` into a global shortcut.
+fn parse_shortcut(combo: &str) -> Result<Shortcut, String> {
+    let shortcut_str = hotkey::config_combo_to_shortcut(combo)
+        .map_err(|e| format!("invalid hotkey config: {e}"))?;
+    shortcut_str
+        .parse()
+        .map_err(|e| format!("failed to parse shortcut '{shortcut_str}': {e}"))
+}
+
+fn handle_shortcut_event(app: &AppHandle, state: ShortcutState, translate: bool) {
     // How it works (WisprFlow-style dual mode):
     // - Hold the hotkey (>= 300 ms): push-to-talk — release stops recording.
     // - Quick tap (< 300 ms): toggle mode — tap again to stop.
+    // - `auto` mode: every press starts toggle mode, and silence stops it (auto_stop.rs).
     // The pure decision logic lives in hotkey::resolve_shortcut_action (unit-tested).
+    // The translate hotkey behaves the same; recordings it starts are translated.
 
     let action = {
         let app_state = app.state::<AppState>();
@@ -124,6 +166,7 @@ fn handle_shortcut_event(app: &AppHandle, state: ShortcutState) {
         let is_idle = lock.status == AppStatus::Idle;
         let is_recording = lock.status == AppStatus::Recording;
         let held_ms = lock.press_instant.map(|t| t.elapsed().as_millis());
+        let auto_mode = lock.config.general.mode == MODE_AUTO;
 
         let action = hotkey::resolve_shortcut_action(
             pressed,
@@ -132,6 +175,7 @@ fn handle_shortcut_event(app: &AppHandle, state: ShortcutState) {
             lock.toggle_shortcut_held,
             lock.toggle_active,
             held_ms,
+            auto_mode,
         );
 
         // Apply state mutations that must happen inside the lock.
@@ -167,7 +211,7 @@ fn handle_shortcut_event(app: &AppHandle, state: ShortcutState) {
     tauri::async_runtime::spawn(async move {
         let out = match action {
             hotkey::ShortcutAction::Start => {
-                pipeline::start_recording_inner(app_clone.clone()).await
+                pipeline::start_recording_inner(app_clone.clone(), translate.then_some(true)).await
             }
             hotkey::ShortcutAction::Stop => pipeline::stop_recording_inner(app_clone.clone())
                 .await
@@ -176,17 +220,22 @@ fn handle_shortcut_event(app: &AppHandle, state: ShortcutState) {
         };
 
         if let Err(err) = out {
-            dlog!("shortcut pipeline failed: {err}");
-            set_widget_state(&app_clone, "error", Some(err.clone()));
-            if let Ok(mut lock) = app_clone.state::<AppState>().inner_state.lock() {
-                lock.reset_to_idle();
-                lock.toggle_shortcut_held = false;
-                let _ = lock.recorder.stop();
-            };
+            report_pipeline_error(&app_clone, err);
         }
     });
 }
 
+/// Show a failed start/stop in the widget and put the app back to idle.
+pub(crate) fn report_pipeline_error(app: &AppHandle, err: String) {
+    dlog!("shortcut pipeline failed: {err}");
+    set_widget_state(app, "error", Some(err));
+    if let Ok(mut lock) = app.state::<AppState>().inner_state.lock() {
+        lock.reset_to_idle();
+        lock.toggle_shortcut_held = false;
+        let _ = lock.recorder.stop();
+    };
+}
+
 // ── App entry point ─────────────────────────────────────────────────────
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -197,14 +246,20 @@ pub fn run() {
     });
 
     let hotkey_combo = config.general.hotkey.clone();
+    let translate_shortcut = match config.general.translate_hotkey.trim() {
+        "" => None,
+        combo => parse_shortcut(combo)
+            .map_err(|e| dlog!("translate hotkey ignored: {e}"))
+            .ok(),
+    };
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
                 .with_handler(move |app, shortcut, event| {
-                    let _ = shortcut;
-                    handle_shortcut_event(app, event.state);
+                    let translate = translate_shortcut.as_ref() == Some(shortcut);
+                    handle_shortcut_event(app, event.state, translate);
                 })
                 .build(),
         )
@@ -218,14 +273,35 @@ pub fn run() {
             set_recording_mode,
             set_prompt_mode,
             set_model,
+            set_language,
+            set_translate,
+            set_decoding_preset,
+            set_style_profile,
+            set_escalation,
             check_model,
+            verify_model,
+            benchmark_model,
+            set_whisper_threads,
+            list_models,
+            import_model,
             download_model,
+            cancel_model_download,
             set_dictionary_words,
-            set_dictionary_replacements
+            set_pinned_words,
+            preview_initial_prompt,
+            set_dictionary_replacements,
+            list_input_devices,
+            set_audio_device,
+            set_warm_mic,
+            set_live_transcription,
+            set_carry_context,
+            transcribe_file
         ])
         .setup(move |app| {
             init_logging();
             build_tray(app.handle())?;
+            device_watcher::spawn_device_watcher(app.handle().clone());
+            watchdog::spawn_recording_watchdog(app.handle().clone());
 
             // Prompt for Accessibility permission on first launch so CGEvent paste works.
             // On subsequent launches where permission is already granted this is a no-op.
@@ -238,14 +314,15 @@ pub fn run() {
                 );
             }
 
-            let shortcut_str = hotkey::config_combo_to_shortcut(&hotkey_combo)
-                .map_err(|e| format!("invalid hotkey config: {e}"))?;
-            let shortcut: tauri_plugin_global_shortcut::Shortcut = shortcut_str
-                .parse()
-                .map_err(|e| format!("failed to parse shortcut '{shortcut_str}': {e}"))?;
+            let shortcut = parse_shortcut(&hotkey_combo)?;
             app.global_shortcut()
                 .register(shortcut)
-                .map_err(|e| format!("failed to register global shortcut '{shortcut_str}': {e}"))?;
+                .map_err(|e| format!("failed to register global shortcut '{hotkey_combo}': {e}"))?;
+            if let Some(translate_shortcut) = translate_shortcut {
+                if let Err(e) = app.global_shortcut().register(translate_shortcut) {
+                    dlog!("failed to register translate hotkey: {e}");
+                }
+            }
 
             if let Some(widget) = app.get_webview_window("widget") {
                 let _ = widget.hide();
@@ -253,6 +330,11 @@ pub fn run() {
             }
 
             let state = app.state::<AppState>();
+            let verify_on_startup = state
+                .inner_state
+                .lock()
+                .map(|s| s.config.model.verify_on_startup)
+                .unwrap_or(false);
             let needs_model = state
                 .inner_state
                 .lock()
@@ -262,6 +344,19 @@ pub fn run() {
             if needs_model {
                 show_main_window(app.handle());
             } else {
+                if verify_on_startup {
+                    let app_handle = app.handle().clone();
+                    tauri::async_runtime::spawn(async move {
+                        match pipeline::verify_model_inner(app_handle.clone()).await {
+                            Ok(status) if status.valid == Some(false) => {
+                                show_main_window(&app_handle);
+                            }
+                            Ok(_) => {}
+                            Err(e) => dlog!("startup: model verification failed: {e}"),
+                        }
+                    });
+                }
+
                 // Pre-load WhisperContext in background so first recording is instant.
                 // Block recording until pre-load finishes to avoid 30s mutex contention.
                 state.model_ready.store(false, Ordering::SeqCst);
@@ -0,0 +1,258 @@
+//! Live partial transcription while recording.
+//!
+//! With `general.live_transcription` on, a thread re-transcribes the uncommitted tail of
+//! the growing recording every [`LIVE_INTERVAL`] with the cached Whisper context and emits
+//! `partial_transcript` to the widget. Segments that two consecutive passes agree on (all
+//! but the last, which is still growing) are committed: their text is final and their
+//! audio is never transcribed again. When the recording stops, the final pass only covers
+//! the audio after the committed prefix, so end-to-end latency stays a few seconds of
+//! audio regardless of how long the dictation was.
+
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::carryover;
+use crate::chunking::normalize_word;
+use crate::dlog;
+use crate::state::{AppState, AppStatus, PartialTranscriptPayload};
+use crate::whisper::{self, TimedSegment, TranscribeOptions};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Time between the starts of two passes. Passes that take longer run back to back.
+const LIVE_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// How often the thread checks for the end of the recording while waiting.
+const STOP_POLL: Duration = Duration::from_millis(50);
+
+/// Audio needed before a pass; Whisper output on less than a second is mostly noise.
+const MIN_WINDOW_SAMPLES: usize = TARGET_SAMPLE_RATE as usize;
+
+/// A window this long is committed up to its last segment without waiting for agreement,
+/// so passes stay fast and well inside Whisper's 30 s window.
+const MAX_WINDOW_SAMPLES: usize = 20 * TARGET_SAMPLE_RATE as usize;
+
+/// Text that live transcription has committed, and the recording samples it covers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LivePrefix {
+    pub text: String,
+    pub samples: usize,
+}
+
+/// Commits segments once consecutive passes agree on them.
+#[derive(Default)]
+struct Agreement {
+    prefix: LivePrefix,
+    /// Uncommitted segment texts of the previous pass.
+    previous: Vec<String>,
+}
+
+impl Agreement {
+    /// Take a pass over the window starting at `prefix.samples` (`window_len` samples long).
+    /// Returns the text that is still tentative.
+    fn update(&mut self, segments: &[TimedSegment], window_len: usize) -> String {
+        let growing = segments.len().saturating_sub(1);
+        let mut commit = segments[..growing]
+            .iter()
+            .zip(&self.previous)
+            .take_while(|(segment, previous)| same_words(&segment.text, previous))
+            .count();
+        if window_len >= MAX_WINDOW_SAMPLES {
+            commit = growing;
+        }
+        // A segment without a usable end time can't be cut at; keep it tentative.
+        while commit > 0 && segments[commit - 1].end == 0 {
+            commit -= 1;
+        }
+
+        if commit > 0 {
+            for segment in &segments[..commit] {
+                append_text(&mut self.prefix.text, &segment.text);
+            }
+            self.prefix.samples += segments[commit - 1].end;
+        }
+        self.previous = segments[commit..].iter().map(|s| s.text.clone()).collect();
+
+        let mut tentative = String::new();
+        for text in &self.previous {
+            append_text(&mut tentative, text);
+        }
+        tentative
+    }
+}
+
+fn same_words(a: &str, b: &str) -> bool {
+    let a = a
+        .split_whitespace()
+        .map(normalize_word)
+        .filter(|w| !w.is_empty());
+    let b = b
+        .split_whitespace()
+        .map(normalize_word)
+        .filter(|w| !w.is_empty());
+    a.eq(b)
+}
+
+fn append_text(text: &mut String, next: &str) {
+    let next = next.trim();
+    if next.is_empty() {
+        return;
+    }
+    if !text.is_empty() {
+        text.push(' ');
+    }
+    text.push_str(next);
+}
+
+/// Handle to the live transcription thread of one recording.
+pub struct LiveSession {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<LivePrefix>,
+}
+
+impl LiveSession {
+    /// Stop the thread, aborting a pass in flight, and return the committed prefix.
+    /// Blocks until the thread exits; don't call with `inner_state` locked.
+    pub fn finish(self) -> LivePrefix {
+        self.stop.store(true, Ordering::SeqCst);
+        self.handle.join().unwrap_or_default()
+    }
+
+    /// Stop the thread without waiting for it (the recording was cancelled).
+    pub fn cancel(self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Start live passes for the recording that just began. Each pass is conditioned on
+/// `options.context` (text carried over from the previous dictation) and the text
+/// committed so far.
+pub fn spawn_live_session(app: AppHandle, options: TranscribeOptions) -> Option<LiveSession> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let spawned = std::thread::Builder::new()
+        .name("live-transcription".to_string())
+        .spawn({
+            let stop = Arc::clone(&stop);
+            move || run_live_passes(&app, options, &stop)
+        });
+
+    match spawned {
+        Ok(handle) => Some(LiveSession { stop, handle }),
+        Err(e) => {
+            dlog!("live: failed to start live transcription: {e}");
+            None
+        }
+    }
+}
+
+fn run_live_passes(
+    app: &AppHandle,
+    mut options: TranscribeOptions,
+    stop: &Arc<AtomicBool>,
+) -> LivePrefix {
+    let state = app.state::<AppState>();
+    let carried = std::mem::take(&mut options.context);
+    let mut agreement = Agreement::default();
+    let mut next_pass = Instant::now() + LIVE_INTERVAL;
+
+    loop {
+        while Instant::now() < next_pass {
+            if stop.load(Ordering::SeqCst) {
+                return agreement.prefix;
+            }
+            std::thread::sleep(STOP_POLL);
+        }
+        next_pass = Instant::now() + LIVE_INTERVAL;
+
+        let (window, speech) = match state.inner_state.lock() {
+            Ok(inner) if inner.status == AppStatus::Recording => (
+                inner.recorder.recorded_since(agreement.prefix.samples),
+                inner.recorder.speech_since(agreement.prefix.samples),
+            ),
+            _ => break,
+        };
+        if window.len() < MIN_WINDOW_SAMPLES {
+            continue;
+        }
+
+        // Never load the model or queue behind a final transcription from here.
+        let Ok(ctx_lock) = state.whisper_ctx.try_lock() else {
+            continue;
+        };
+        let Some(ctx) = ctx_lock.as_ref() else {
+            continue;
+        };
+        options.context = carryover::join_context(&carried, &agreement.prefix.text);
+        let segments =
+            whisper::transcribe_segments(&ctx.0, &window, &speech, &options, Arc::clone(stop));
+        drop(ctx_lock);
+
+        let segments = match segments {
+            Ok(segments) => segments,
+            Err(err) => {
+                if !stop.load(Ordering::SeqCst) {
+                    dlog!("live: pass over {} samples failed: {err}", window.len());
+                }
+                continue;
+            }
+        };
+
+        let tentative = agreement.update(&segments, window.len());
+        let _ = app.emit(
+            "partial_transcript",
+            PartialTranscriptPayload {
+                committed: agreement.prefix.text.clone(),
+                tentative,
+            },
+        );
+    }
+
+    agreement.prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(parts: &[(&str, usize)]) -> Vec<TimedSegment> {
+        parts
+            .iter()
+            .map(|&(text, end)| TimedSegment {
+                text: text.to_string(),
+                end,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn commits_segments_two_passes_agree_on() {
+        let mut agreement = Agreement::default();
+        let first = segments(&[("Hello there.", 16_000), ("How are", 28_000)]);
+        assert_eq!(agreement.update(&first, 30_000), "Hello there. How are");
+        assert_eq!(agreement.prefix, LivePrefix::default());
+
+        let second = segments(&[("hello there", 16_500), ("How are you doing?", 44_000)]);
+        assert_eq!(agreement.update(&second, 46_000), "How are you doing?");
+        assert_eq!(agreement.prefix.text, "hello there");
+        assert_eq!(agreement.prefix.samples, 16_500);
+    }
+
+    #[test]
+    fn the_last_segment_is_never_committed_by_agreement() {
+        let mut agreement = Agreement::default();
+        let pass = segments(&[("Still talking", 20_000)]);
+        agreement.update(&pass, 20_000);
+        assert_eq!(agreement.update(&pass, 24_000), "Still talking");
+        assert!(agreement.prefix.text.is_empty());
+    }
+
+    #[test]
+    fn a_long_window_is_committed_without_agreement() {
+        let mut agreement = Agreement::default();
+        let pass = segments(&[("One.", 100_000), ("Two.", 200_000), ("Thr", 320_000)]);
+        assert_eq!(agreement.update(&pass, MAX_WINDOW_SAMPLES), "Thr");
+        assert_eq!(agreement.prefix.text, "One. Two.");
+        assert_eq!(agreement.prefix.samples, 200_000);
+    }
+}
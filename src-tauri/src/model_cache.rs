@@ -0,0 +1,131 @@
+//! Cache of extra loaded Whisper models, besides the main one in `AppState::whisper_ctx`.
+//!
+//! Used for confidence escalation (see `pipeline`): a larger model re-transcribes unclear
+//! dictations, and stays loaded so the next escalation doesn't pay the load time again.
+//! Loaded models take about as much memory as their files, so the cache holds models up to
+//! a byte budget and unloads the least recently used ones to make room for another.
+
+use crate::dlog;
+
+/// Loaded values keyed by model path, least recently used first.
+pub struct ModelCache<T> {
+    entries: Vec<CachedModel<T>>,
+    capacity_bytes: u64,
+}
+
+struct CachedModel<T> {
+    key: String,
+    bytes: u64,
+    value: T,
+}
+
+impl<T> ModelCache<T> {
+    pub fn new(capacity_bytes: u64) -> Self {
+        Self {
+            entries: Vec::new(),
+            capacity_bytes,
+        }
+    }
+
+    /// Change the budget, unloading least recently used models that no longer fit.
+    pub fn set_capacity(&mut self, capacity_bytes: u64) {
+        self.capacity_bytes = capacity_bytes;
+        self.evict_to_fit(0);
+    }
+
+    pub fn used_bytes(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.bytes).sum()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.iter().any(|entry| entry.key == key)
+    }
+
+    /// The cached value for `key`, marked most recently used, or `load()`'s result for a
+    /// model of `bytes`, cached after unloading what it needs room from. Fails without
+    /// loading when the model alone is over the budget.
+    pub fn get_or_load(
+        &mut self,
+        key: &str,
+        bytes: u64,
+        load: impl FnOnce() -> Result<T, String>,
+    ) -> Result<&T, String> {
+        if let Some(at) = self.entries.iter().position(|entry| entry.key == key) {
+            let entry = self.entries.remove(at);
+            self.entries.push(entry);
+        } else {
+            if bytes > self.capacity_bytes {
+                return Err(format!(
+                    "{key} needs about {} MB, more than the {} MB model cache limit",
+                    bytes / 1_000_000,
+                    self.capacity_bytes / 1_000_000
+                ));
+            }
+            self.evict_to_fit(bytes);
+            let value = load()?;
+            self.entries.push(CachedModel {
+                key: key.to_string(),
+                bytes,
+                value,
+            });
+        }
+        Ok(&self.entries.last().expect("entry is last").value)
+    }
+
+    fn evict_to_fit(&mut self, incoming: u64) {
+        while !self.entries.is_empty() && self.used_bytes() + incoming > self.capacity_bytes {
+            let evicted = self.entries.remove(0);
+            dlog!(
+                "model cache: unloaded {} ({} MB)",
+                evicted.key,
+                evicted.bytes / 1_000_000
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1_000_000;
+
+    fn load(value: u32) -> impl FnOnce() -> Result<u32, String> {
+        move || Ok(value)
+    }
+
+    #[test]
+    fn evicts_least_recently_used_models_to_fit() {
+        let mut cache = ModelCache::new(1000 * MB);
+        assert_eq!(cache.get_or_load("small", 400 * MB, load(1)), Ok(&1));
+        assert_eq!(cache.get_or_load("medium", 500 * MB, load(2)), Ok(&2));
+        // A hit doesn't load again and makes "small" the most recently used.
+        assert_eq!(
+            cache.get_or_load("small", 400 * MB, || Err("reloaded".to_string())),
+            Ok(&1)
+        );
+        assert_eq!(cache.get_or_load("turbo", 600 * MB, load(3)), Ok(&3));
+        assert!(!cache.contains("medium"));
+        assert!(cache.contains("small"));
+        assert_eq!(cache.used_bytes(), 1000 * MB);
+
+        cache.set_capacity(700 * MB);
+        assert!(!cache.contains("small"));
+        assert!(cache.contains("turbo"));
+    }
+
+    #[test]
+    fn refuses_a_model_over_the_budget_without_evicting() {
+        let mut cache = ModelCache::new(1000 * MB);
+        cache.get_or_load("small", 400 * MB, load(1)).unwrap();
+        let err = cache.get_or_load("large", 1500 * MB, load(2)).unwrap_err();
+        assert!(err.contains("1500 MB"), "{err}");
+        assert!(cache.contains("small"));
+
+        // A failed load caches nothing.
+        assert!(cache
+            .get_or_load("broken", 100 * MB, || Err("bad file".to_string()))
+            .is_err());
+        assert!(!cache.contains("broken"));
+    }
+}
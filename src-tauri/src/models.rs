@@ -0,0 +1,420 @@
+//! Whisper model catalog.
+//!
+//! The built-in catalog lists the ggml models published in the whisper.cpp Hugging Face
+//! repository, including a few quantized variants. `~/.dravis-flow/models.toml` can add
+//! entries or replace built-in ones by name:
+//!
+//! ```toml
+//! [[model]]
+//! name = "large-v3-turbo-q5_0"
+//! url = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo-q5_0.bin"
+//! size_mb = 547
+//! multilingual = true
+//! quantization = "q5_0"
+//! ```
+//!
+//! An entry with `path` uses that file wherever it is instead of one in the models
+//! directory. `import_model` adds entries for local files (see [`import_model_file`]).
+//!
+//! The catalog is read on first use and again after an import; other edits to
+//! `models.toml` apply after a restart.
+
+use crate::config::{config_dir, expand_home, MODEL_BASE_EN};
+use crate::dlog;
+use crate::{verify, whisper};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+const HF_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// Built-in models: name, approximate size in MB, multilingual, quantization.
+const BUILTIN_MODELS: &[(&str, u64, bool, Option<&str>)] = &[
+    ("tiny.en", 75, false, None),
+    ("tiny", 75, true, None),
+    ("base.en", 142, false, None),
+    ("base", 142, true, None),
+    ("small.en", 466, false, None),
+    ("small", 466, true, None),
+    ("medium.en", 1500, false, None),
+    ("medium", 1500, true, None),
+    ("large-v3-turbo", 809, true, None),
+    ("base.en-q5_1", 57, false, Some("q5_1")),
+    ("small.en-q5_1", 181, false, Some("q5_1")),
+    ("medium.en-q5_0", 514, false, Some("q5_0")),
+    ("medium-q5_0", 514, true, Some("q5_0")),
+    ("large-v3-turbo-q5_0", 547, true, Some("q5_0")),
+    ("large-v3-turbo-q8_0", 834, true, Some("q8_0")),
+];
+
+/// One downloadable (or locally provided) Whisper model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelEntry {
+    pub name: String,
+    /// File name inside the models directory. Defaults to `ggml-<name>.bin`.
+    #[serde(default)]
+    pub filename: String,
+    /// Download URL; empty for models that can't be downloaded.
+    #[serde(default)]
+    pub url: String,
+    /// Approximate download size.
+    #[serde(default)]
+    pub size_mb: u64,
+    /// Expected SHA-256 of the file (lowercase hex), when known.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Multilingual models can transcribe other languages, detect the language and translate.
+    #[serde(default)]
+    pub multilingual: bool,
+    /// ggml quantization such as `"q5_0"` or `"q8_0"`; `None` for full precision.
+    #[serde(default)]
+    pub quantization: Option<String>,
+    /// Model file outside the models directory (`~/` allowed). Overrides `filename`.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+impl ModelEntry {
+    fn builtin(name: &str, size_mb: u64, multilingual: bool, quantization: Option<&str>) -> Self {
+        let filename = format!("ggml-{name}.bin");
+        Self {
+            name: name.to_string(),
+            url: format!("{HF_BASE_URL}/{filename}"),
+            filename,
+            size_mb,
+            sha256: None,
+            multilingual,
+            quantization: quantization.map(str::to_string),
+            path: None,
+        }
+    }
+
+    /// Where the model file lives.
+    pub fn file_path(&self, models_dir: &Path) -> PathBuf {
+        match &self.path {
+            Some(path) => expand_home(path),
+            None => models_dir.join(&self.filename),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ModelsFile {
+    #[serde(default, rename = "model")]
+    models: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModelCatalog {
+    entries: Vec<ModelEntry>,
+}
+
+impl ModelCatalog {
+    pub fn builtin() -> Self {
+        Self {
+            entries: BUILTIN_MODELS
+                .iter()
+                .map(|&(name, size, multilingual, quantization)| {
+                    ModelEntry::builtin(name, size, multilingual, quantization)
+                })
+                .collect(),
+        }
+    }
+
+    /// Built-in catalog extended with `models.toml` from `dir`, if present. A broken file is
+    /// logged and ignored.
+    pub fn load(dir: &Path) -> Self {
+        let mut catalog = Self::builtin();
+        let path = dir.join("models.toml");
+        let Ok(content) = fs::read_to_string(&path) else {
+            return catalog;
+        };
+        match toml::from_str::<ModelsFile>(&content) {
+            Ok(file) => {
+                for entry in file.models {
+                    if let Err(err) = catalog.add(entry) {
+                        dlog!("models: skipping entry in {}: {err}", path.display());
+                    }
+                }
+            }
+            Err(e) => dlog!("models: failed parsing {}: {e}", path.display()),
+        }
+        catalog
+    }
+
+    /// Add `entry`, replacing a model of the same name.
+    fn add(&mut self, mut entry: ModelEntry) -> Result<(), String> {
+        entry.name = entry.name.trim().to_string();
+        if entry.name.is_empty() {
+            return Err("model entry without a name".to_string());
+        }
+        if entry.filename.trim().is_empty() {
+            entry.filename = format!("ggml-{}.bin", entry.name);
+        }
+        let filename = entry.filename.trim();
+        if filename.contains(['/', '\\']) || filename.starts_with('.') {
+            return Err(format!(
+                "{}: filename must be a plain file name, got '{filename}'",
+                entry.name
+            ));
+        }
+        entry.filename = filename.to_string();
+        entry.path = entry
+            .path
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty());
+        entry.sha256 = entry
+            .sha256
+            .map(|hash| hash.trim().to_lowercase())
+            .filter(|hash| !hash.is_empty());
+
+        match self.entries.iter_mut().find(|e| e.name == entry.name) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+        Ok(())
+    }
+
+    pub fn find(&self, name: &str) -> Option<&ModelEntry> {
+        let name = name.trim();
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// The entry for `name`, or the default model for unknown names.
+    pub fn resolve(&self, name: &str) -> &ModelEntry {
+        self.find(name)
+            .or_else(|| self.find(MODEL_BASE_EN))
+            .unwrap_or(&self.entries[0])
+    }
+
+    pub fn entries(&self) -> &[ModelEntry] {
+        &self.entries
+    }
+}
+
+static CATALOG: RwLock<Option<Arc<ModelCatalog>>> = RwLock::new(None);
+
+/// The app's model catalog, loaded from the config dir on first use.
+pub fn catalog() -> Arc<ModelCatalog> {
+    if let Some(catalog) = CATALOG.read().ok().and_then(|c| c.clone()) {
+        return catalog;
+    }
+    reload_catalog()
+}
+
+/// Read `models.toml` again, e.g. after an import added to it.
+fn reload_catalog() -> Arc<ModelCatalog> {
+    let catalog = Arc::new(ModelCatalog::load(&config_dir()));
+    if let Ok(mut lock) = CATALOG.write() {
+        *lock = Some(Arc::clone(&catalog));
+    }
+    catalog
+}
+
+/// Append `entry` to `models.toml` and reload the catalog.
+fn save_custom_model(entry: &ModelEntry) -> Result<(), String> {
+    let file = ModelsFile {
+        models: vec![entry.clone()],
+    };
+    let text =
+        toml::to_string_pretty(&file).map_err(|e| format!("failed serializing model: {e}"))?;
+    let path = config_dir().join("models.toml");
+    let mut out = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("failed opening {}: {e}", path.display()))?;
+    out.write_all(format!("\n{text}").as_bytes())
+        .map_err(|e| format!("failed writing {}: {e}", path.display()))?;
+    reload_catalog();
+    Ok(())
+}
+
+/// Name for an imported file: the given one, or the file stem without a `ggml-` prefix.
+fn import_name(source: &Path, name: Option<&str>) -> Result<String, String> {
+    let name = match name.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => name.to_string(),
+        None => {
+            let stem = source
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default();
+            stem.strip_prefix("ggml-").unwrap_or(stem).to_string()
+        }
+    };
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid || name.starts_with('.') {
+        return Err(format!(
+            "invalid model name '{name}'; use letters, digits, '-', '_' and '.'"
+        ));
+    }
+    Ok(name)
+}
+
+/// Add a local ggml/gguf file to the catalog. It's loaded once to check that Whisper can
+/// use it (and whether it's multilingual), then copied into `models_dir`, or symlinked
+/// there with `link` (where supported). Blocking; loading a large model takes seconds.
+pub fn import_model_file(
+    source: &Path,
+    name: Option<&str>,
+    models_dir: &Path,
+    link: bool,
+) -> Result<ModelEntry, String> {
+    if !source.is_file() {
+        return Err(format!("{} is not a file", source.display()));
+    }
+    let name = import_name(source, name)?;
+    if catalog().find(&name).is_some() {
+        return Err(format!("a model named '{name}' already exists"));
+    }
+
+    let source_str = source
+        .to_str()
+        .ok_or_else(|| "invalid model path".to_string())?;
+    let ctx = whisper::load_context(source_str)
+        .map_err(|e| format!("{} is not a usable Whisper model: {e}", source.display()))?;
+    let multilingual = ctx.is_multilingual();
+    drop(ctx);
+
+    let extension = source.extension().and_then(|e| e.to_str()).unwrap_or("bin");
+    let filename = format!("ggml-{name}.{extension}");
+    let dest = models_dir.join(&filename);
+    if dest.exists() {
+        return Err(format!("{} already exists", dest.display()));
+    }
+    fs::create_dir_all(models_dir).map_err(|e| {
+        format!(
+            "failed to create model directory {}: {e}",
+            models_dir.display()
+        )
+    })?;
+    if link {
+        link_file(source, &dest)?;
+    } else {
+        let part = dest.with_extension(format!("{extension}.part"));
+        fs::copy(source, &part)
+            .and_then(|_| fs::rename(&part, &dest))
+            .map_err(|e| format!("failed copying model to {}: {e}", dest.display()))?;
+    }
+
+    let size = fs::metadata(&dest).map(|m| m.len()).unwrap_or(0);
+    let entry = ModelEntry {
+        name,
+        filename,
+        url: String::new(),
+        size_mb: size / 1_000_000,
+        sha256: Some(verify::sha256_file(&dest)?),
+        multilingual,
+        quantization: None,
+        path: None,
+    };
+    save_custom_model(&entry)?;
+    dlog!(
+        "models: imported {} as '{}' (multilingual: {multilingual})",
+        source.display(),
+        entry.name
+    );
+    Ok(entry)
+}
+
+#[cfg(unix)]
+fn link_file(source: &Path, dest: &Path) -> Result<(), String> {
+    let source = fs::canonicalize(source)
+        .map_err(|e| format!("failed resolving {}: {e}", source.display()))?;
+    std::os::unix::fs::symlink(&source, dest)
+        .map_err(|e| format!("failed linking {}: {e}", dest.display()))
+}
+
+#[cfg(not(unix))]
+fn link_file(_source: &Path, _dest: &Path) -> Result<(), String> {
+    Err("linking models isn't supported on this platform; import a copy instead".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_catalog_covers_sizes_and_quantized_variants() {
+        let catalog = ModelCatalog::builtin();
+        let tiny = catalog.find("tiny").expect("tiny");
+        assert!(tiny.multilingual);
+        assert_eq!(tiny.url, format!("{HF_BASE_URL}/ggml-tiny.bin"));
+        assert!(!catalog.find(" medium.en ").unwrap().multilingual);
+        let q5 = catalog.find("large-v3-turbo-q5_0").unwrap();
+        assert_eq!(q5.filename, "ggml-large-v3-turbo-q5_0.bin");
+        assert_eq!(q5.quantization.as_deref(), Some("q5_0"));
+        assert!(catalog.find("huge").is_none());
+        assert_eq!(catalog.resolve("huge").name, MODEL_BASE_EN);
+    }
+
+    #[test]
+    fn models_file_adds_and_replaces_entries() {
+        let file: ModelsFile = toml::from_str(
+            r#"
+            [[model]]
+            name = "domain-small"
+            url = "https://example.com/domain-small.bin"
+            multilingual = true
+            sha256 = " ABC123 "
+
+            [[model]]
+            name = "base"
+            filename = "ggml-base-mirror.bin"
+            url = "https://mirror.example.com/ggml-base.bin"
+            multilingual = true
+
+            [[model]]
+            name = "escape"
+            filename = "../outside.bin"
+            "#,
+        )
+        .expect("parse models file");
+
+        let mut catalog = ModelCatalog::builtin();
+        let count = catalog.entries().len();
+        let results: Vec<_> = file.models.into_iter().map(|e| catalog.add(e)).collect();
+        assert!(results[0].is_ok() && results[1].is_ok());
+        assert!(results[2].is_err());
+        assert_eq!(catalog.entries().len(), count + 1);
+
+        let custom = catalog.find("domain-small").unwrap();
+        assert_eq!(custom.filename, "ggml-domain-small.bin");
+        assert_eq!(custom.sha256.as_deref(), Some("abc123"));
+        assert_eq!(
+            catalog.find("base").unwrap().filename,
+            "ggml-base-mirror.bin"
+        );
+    }
+
+    #[test]
+    fn entries_with_a_path_live_outside_the_models_dir() {
+        let mut catalog = ModelCatalog::builtin();
+        let entry: ModelEntry =
+            toml::from_str("name = \"domain\"\npath = \"/data/whisper/domain.gguf\"\n")
+                .expect("parse entry");
+        catalog.add(entry).unwrap();
+        let models_dir = Path::new("/models");
+        assert_eq!(
+            catalog.find("domain").unwrap().file_path(models_dir),
+            PathBuf::from("/data/whisper/domain.gguf")
+        );
+        assert_eq!(
+            catalog.find("base").unwrap().file_path(models_dir),
+            PathBuf::from("/models/ggml-base.bin")
+        );
+    }
+
+    #[test]
+    fn import_names_come_from_the_file_stem() {
+        let source = Path::new("/tmp/ggml-legal-small.en.bin");
+        assert_eq!(import_name(source, None).unwrap(), "legal-small.en");
+        assert_eq!(import_name(source, Some(" mine ")).unwrap(), "mine");
+        assert!(import_name(source, Some("../escape")).is_err());
+    }
+}
@@ -1,30 +1,58 @@
 //! Recording pipeline: start → capture audio → stop → transcribe → format → [structure] → paste.
 //!
 //! This is the core flow. `stop_recording_inner` orchestrates the full chain:
-//! silence trim → Whisper transcription → formatter cleanup → dictionary replacements
-//! → optional Prompt Mode (cloud LLM) → clipboard paste at cursor.
+//! VAD voiced-region extraction → Whisper transcription → formatter cleanup
+//! → dictionary replacements → optional Prompt Mode (cloud LLM) → clipboard paste at cursor.
 //! On any Prompt Mode error, falls back to the formatted text (never loses transcription).
-
-use crate::config::model_file_path;
-use crate::state::{with_state, AppState, AppStatus, SendWhisperCtx};
-use crate::{dlog, set_widget_state};
-use crate::{formatter, prompt, whisper};
-use std::fs::{self, File};
-use std::io::{Read, Write};
+//! With live transcription (see `live`), the text committed while recording is reused and
+//! Whisper only runs on the audio after it. Translated recordings paste English; their
+//! source-language text is transcribed afterwards, off the critical path, for the log.
+
+use crate::app_setup::resize_widget_window;
+use crate::audio::{VoicedAudio, TARGET_SAMPLE_RATE};
+use crate::carryover::{self, LastDictation};
+use crate::config::{
+    canonical_model_name, check_model_language, check_model_translate, escalation_model_path,
+    model_file_path, normalized_auto_stop_silence_ms, normalized_carry_context_secs,
+    normalized_escalation_min_probability, normalized_model_name, whisper_threads,
+    ReplacementEntry, LANGUAGE_AUTO,
+};
+use crate::live::{self, LivePrefix};
+use crate::state::{
+    with_state, AppState, AppStatus, BenchmarkReport, InnerState, ModelStatus, SendWhisperCtx,
+    TranscriptionProgressPayload,
+};
+use crate::{dlog, set_widget_state, set_widget_state_with_language};
+use crate::{
+    auto_stop, benchmark, decode, download, formatter, glossary, hallucination, models, prompt,
+    verify, whisper,
+};
+use std::path::PathBuf;
+use std::sync::MutexGuard;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager};
-use whisper::WhisperEngine;
+use whisper::{TranscribeOptions, Transcript, WhisperEngine};
 
 /// Minimum samples required to attempt transcription (~1s at 16 kHz).
 const MIN_TRANSCRIBE_SAMPLES: usize = 16_000;
 
-pub async fn start_recording_inner(app: AppHandle) -> Result<(), String> {
+/// Start a recording. `translate` overrides `general.translate` for this recording (the
+/// translate hotkey passes `Some(true)`).
+pub async fn start_recording_inner(app: AppHandle, translate: Option<bool>) -> Result<(), String> {
     let state = app.state::<AppState>();
 
     if !state.model_ready.load(std::sync::atomic::Ordering::SeqCst) {
         return Err("Model is still loading, please wait...".to_string());
     }
 
-    let language = with_state(&state, |inner| {
+    // Capture which app had focus before we show the widget.
+    // We'll reactivate it just before pasting so Cmd+V reaches the right target.
+    #[cfg(target_os = "macos")]
+    let target_pid = crate::injector::get_frontmost_app_pid();
+    #[cfg(not(target_os = "macos"))]
+    let target_pid: Option<i32> = None;
+
+    let start = with_state(&state, |inner| {
         if inner.status != AppStatus::Idle {
             return Ok(None);
         }
@@ -34,36 +62,64 @@ pub async fn start_recording_inner(app: AppHandle) -> Result<(), String> {
             inner.reset_to_idle();
             return Err("Whisper model is missing. Download model first.".to_string());
         }
+        let translate = translate.unwrap_or(inner.config.general.translate);
+        let model = &inner.config.model.name;
+        if let Err(err) = check_model_language(model, &inner.config.general.language)
+            .and_then(|_| check_model_translate(model, translate))
+        {
+            inner.reset_to_idle();
+            return Err(err);
+        }
 
         inner.status = AppStatus::Recording;
-        Ok(Some(inner.config.general.language.clone()))
+        inner.translate = translate;
+        dlog!("pipeline: captured previous app pid = {:?}", target_pid);
+        inner.previous_app_pid = target_pid;
+        inner.carried_context = carried_context(inner, target_pid, translate);
+        let auto_stop = (inner.config.general.mode == crate::MODE_AUTO).then(|| {
+            Duration::from_millis(normalized_auto_stop_silence_ms(
+                inner.config.general.auto_stop_silence_ms,
+            ) as u64)
+        });
+        let live = inner
+            .config
+            .general
+            .live_transcription
+            .then(|| transcribe_options(inner, translate));
+        Ok(Some((auto_stop, live)))
     })?;
 
-    if language.is_none() {
+    let Some((auto_stop, live)) = start else {
         return Ok(());
-    }
+    };
 
-    // Capture which app had focus before we show the widget.
-    // We'll reactivate it just before pasting so Cmd+V reaches the right target.
-    #[cfg(target_os = "macos")]
-    {
-        let pid = crate::injector::get_frontmost_app_pid();
-        dlog!("pipeline: captured previous app pid = {:?}", pid);
-        with_state(&state, |inner| {
-            inner.previous_app_pid = pid;
-            Ok(())
-        })?;
+    if let Some(widget) = app.get_webview_window("widget") {
+        let _ = resize_widget_window(&widget, live.is_some());
     }
-
     set_widget_state(&app, "recording", None);
 
     let app_for_level = app.clone();
+    let auto_stop_hook = auto_stop.map(|silence| auto_stop::level_hook(app.clone(), silence));
     with_state(&state, |inner| {
         inner.recorder.start(move |level| {
             let _ = app_for_level.emit("audio_level", level);
+            if let Some(hook) = &auto_stop_hook {
+                hook(level);
+            }
         })
     })?;
 
+    if let Some(options) = live {
+        let session = live::spawn_live_session(app.clone(), options);
+        with_state(&state, |inner| {
+            match session {
+                Some(session) if inner.status != AppStatus::Recording => session.cancel(),
+                session => inner.live_session = session,
+            }
+            Ok(())
+        })?;
+    }
+
     Ok(())
 }
 
@@ -89,10 +145,10 @@ pub async fn stop_recording_inner(app: AppHandle) -> Result<String, String> {
 
     let (
         audio,
-        language,
+        live_session,
+        mut options,
         formatting_level,
         model_path_str,
-        dict_words,
         dict_replacements,
         prompt_mode_enabled,
         prompt_mode_provider,
@@ -101,12 +157,12 @@ pub async fn stop_recording_inner(app: AppHandle) -> Result<String, String> {
     ) = with_state(&state, |inner| {
         if inner.status != AppStatus::Recording {
             return Ok((
-                Vec::new(),
-                String::new(),
+                VoicedAudio::default(),
+                None,
+                TranscribeOptions::default(),
                 String::new(),
                 String::new(),
                 Vec::new(),
-                Vec::new(),
                 false,
                 String::new(),
                 String::new(),
@@ -115,17 +171,23 @@ pub async fn stop_recording_inner(app: AppHandle) -> Result<String, String> {
         }
 
         inner.status = AppStatus::Processing;
-        let samples = inner.recorder.stop()?;
+        // With live transcription the recorder is stopped once the live thread has
+        // finished, so its committed audio can be skipped.
+        let live_session = inner.live_session.take();
+        let samples = match live_session {
+            Some(_) => VoicedAudio::default(),
+            None => inner.recorder.stop()?,
+        };
         let model_path = model_file_path(&inner.config)
             .to_str()
             .ok_or_else(|| "invalid model path".to_string())?
             .to_string();
         Ok((
             samples,
-            inner.config.general.language.clone(),
+            live_session,
+            transcribe_options(inner, inner.translate),
             inner.config.formatting.level.clone(),
             model_path,
-            inner.config.dictionary.words.clone(),
             inner.config.dictionary.replacements.clone(),
             inner.config.prompt_mode.enabled,
             inner.config.prompt_mode.provider.clone(),
@@ -134,7 +196,23 @@ pub async fn stop_recording_inner(app: AppHandle) -> Result<String, String> {
         ))
     })?;
 
-    if audio.is_empty() {
+    let (audio, live_prefix) = match live_session {
+        Some(session) => {
+            let prefix = tauri::async_runtime::spawn_blocking(move || session.finish())
+                .await
+                .map_err(|e| format!("live transcription task failed: {e}"))?;
+            let audio = with_state(&state, |inner| inner.recorder.stop_from(prefix.samples))?;
+            dlog!(
+                "pipeline: live transcription committed {} samples, len={}",
+                prefix.samples,
+                prefix.text.len()
+            );
+            (audio, prefix)
+        }
+        None => (audio, LivePrefix::default()),
+    };
+
+    if audio.samples.is_empty() && live_prefix.text.is_empty() {
         set_widget_state(&app, "idle", None);
         with_state(&state, |inner| {
             inner.reset_to_idle();
@@ -143,10 +221,10 @@ pub async fn stop_recording_inner(app: AppHandle) -> Result<String, String> {
         return Ok(String::new());
     }
 
-    if audio.len() < MIN_TRANSCRIBE_SAMPLES {
+    if audio.samples.len() < MIN_TRANSCRIBE_SAMPLES && live_prefix.text.is_empty() {
         dlog!(
             "recording too short ({} samples); skipping transcription",
-            audio.len()
+            audio.samples.len()
         );
         with_state(&state, |inner| {
             inner.reset_to_idle();
@@ -157,41 +235,50 @@ pub async fn stop_recording_inner(app: AppHandle) -> Result<String, String> {
     }
 
     set_widget_state(&app, "processing", Some("Transcribing...".to_string()));
-    dlog!("pipeline: transcribing {} samples", audio.len());
 
-    let app_clone = app.clone();
-    let raw_text = tauri::async_runtime::spawn_blocking(move || {
-        let state = app_clone.state::<AppState>();
-        let mut ctx_lock = state
-            .whisper_ctx
-            .lock()
-            .map_err(|_| "whisper ctx lock poisoned".to_string())?;
-
-        if ctx_lock.is_none() {
-            dlog!("pipeline: loading whisper model (first run)");
-            let ctx = whisper::load_context(&model_path_str)?;
-            *ctx_lock = Some(SendWhisperCtx(ctx));
-            dlog!("pipeline: whisper model loaded and cached");
+    // Too little audio after the committed prefix to be worth a Whisper pass.
+    let transcript = if audio.samples.len() < MIN_TRANSCRIBE_SAMPLES {
+        Transcript {
+            text: live_prefix.text,
+            segments: Vec::new(),
+            detected_language: None,
         }
-
-        whisper::transcribe_with_ctx(
-            &ctx_lock.as_ref().unwrap().0,
-            &audio,
-            &language,
-            &dict_words,
-        )
-    })
-    .await
-    .map_err(|e| format!("transcription task failed: {e}"))??;
-    dlog!("pipeline: transcription done, raw len={}", raw_text.len());
-
-    let formatted = if formatting_level == "basic" {
-        let text = formatter::format_text(&raw_text);
-        formatter::apply_replacements(&text, &dict_replacements)
     } else {
-        let text = raw_text.trim().to_string();
-        formatter::apply_replacements(&text, &dict_replacements)
+        let task = if options.translate {
+            "translating"
+        } else {
+            "transcribing"
+        };
+        dlog!("pipeline: {task} {} samples", audio.samples.len());
+        let source_audio = options.translate.then(|| audio.clone());
+        let escalation = with_state(&state, |inner| Ok(escalation(inner)))?;
+        let escalation_audio = escalation.as_ref().map(|_| audio.clone());
+        options.context = carryover::join_context(&options.context, &live_prefix.text);
+        let mut rest = transcribe_samples(&app, audio, model_path_str, options.clone()).await?;
+        if let (Some(escalation), Some(audio)) = (escalation, escalation_audio) {
+            rest = escalate(&app, escalation, audio, options.clone(), rest).await;
+        }
+        if let Some(source_audio) = source_audio {
+            spawn_source_text_log(&app, source_audio, options);
+        }
+        // Segments cover only the audio after the live prefix.
+        rest.offset(live_prefix.samples);
+        if !live_prefix.text.is_empty() {
+            rest.text = format!("{} {}", live_prefix.text, rest.text)
+                .trim()
+                .to_string();
+        }
+        rest
     };
+    dlog!(
+        "pipeline: transcription done, raw len={}, segments={}",
+        transcript.text.len(),
+        transcript.segments.len()
+    );
+    let _ = app.emit("transcript", &transcript);
+    let detected_language = transcript.detected_language.clone();
+
+    let formatted = format_transcript(&transcript.text, &formatting_level, &dict_replacements);
 
     if formatted.trim().is_empty() {
         dlog!("empty transcript; skipping paste");
@@ -199,16 +286,18 @@ pub async fn stop_recording_inner(app: AppHandle) -> Result<String, String> {
             inner.reset_to_idle();
             Ok(())
         })?;
-        set_widget_state(&app, "idle", None);
+        set_widget_state_with_language(&app, "idle", None, detected_language);
         return Ok(String::new());
     }
+    let glossary_usage = with_state(&state, |inner| Ok(record_glossary_usage(inner, &formatted)))?;
 
-    let mut output_text = formatted;
+    let mut output_text = formatted.clone();
     if prompt_mode_enabled && !prompt_mode_api_key.trim().is_empty() {
-        set_widget_state(
+        set_widget_state_with_language(
             &app,
             "structuring",
             Some("Structuring prompt...".to_string()),
+            detected_language.clone(),
         );
 
         match prompt::structure_prompt(
@@ -263,70 +352,482 @@ pub async fn stop_recording_inner(app: AppHandle) -> Result<String, String> {
     .map_err(|e| format!("injector task failed: {e}"))??;
     dlog!("pipeline: injection done");
 
+    // Saved after the paste and awaited, so saves of back-to-back dictations stay in order.
+    if let Some(usage) = glossary_usage {
+        let saved = tauri::async_runtime::spawn_blocking(move || usage.save()).await;
+        if let Err(err) = saved.map_err(|e| e.to_string()).and_then(|result| result) {
+            dlog!("pipeline: failed saving glossary usage: {err}");
+        }
+    }
+
     with_state(&state, |inner| {
+        inner.last_dictation = Some(LastDictation {
+            text: formatted,
+            finished_at: Instant::now(),
+            app_pid: inner.previous_app_pid,
+            translate: inner.translate,
+        });
         inner.reset_to_idle();
         Ok(())
     })?;
 
-    set_widget_state(&app, "idle", None);
+    set_widget_state_with_language(&app, "idle", None, detected_language);
     Ok(output_text)
 }
 
-pub async fn run_model_download(
-    app: AppHandle,
-    model_path: std::path::PathBuf,
-    model_name: String,
-) -> Result<(), String> {
-    use tauri::Emitter;
-
-    if let Some(parent) = model_path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("failed to create model directory {}: {e}", parent.display()))?;
+/// Transcription options for the current config. `translate` comes from the recording (the
+/// hotkey may override the config).
+pub fn transcribe_options(inner: &InnerState, translate: bool) -> TranscribeOptions {
+    let config = &inner.config;
+    TranscribeOptions {
+        language: config.general.language.clone(),
+        dictionary_words: glossary::ranked_terms(&config.dictionary, &inner.glossary_usage),
+        style: config.style.clone(),
+        context: inner.carried_context.clone(),
+        translate,
+        decoding: config.decoding.settings(),
+        threads: whisper_threads(config.model.threads),
+        hallucinations: config.hallucinations.clone(),
+        no_prompt: false,
     }
+}
 
-    let url = crate::config::model_download_url(&model_name);
+/// Text of the previous dictation to condition a recording that starts now, or empty (see
+/// `carryover`).
+fn carried_context(inner: &InnerState, target_pid: Option<i32>, translate: bool) -> String {
+    let general = &inner.config.general;
+    if !general.carry_context {
+        return String::new();
+    }
+    let max_gap =
+        Duration::from_secs(normalized_carry_context_secs(general.carry_context_secs) as u64);
+    let carried = carryover::carried_text(
+        inner.last_dictation.as_ref(),
+        Instant::now(),
+        max_gap,
+        target_pid,
+        translate,
+    );
+    if let Some(text) = carried {
+        dlog!(
+            "pipeline: carrying {} chars of the previous dictation",
+            text.len()
+        );
+    }
+    carried.unwrap_or_default().to_string()
+}
 
-    tauri::async_runtime::spawn_blocking(move || -> Result<(), String> {
-        let mut response = reqwest::blocking::get(url)
-            .map_err(|e| format!("model download request failed: {e}"))?;
+/// Count the dictionary terms in a finished dictation; frequent terms lead the glossary.
+/// Returns a copy of the counts to save when they changed, so the file is written
+/// outside the state lock.
+fn record_glossary_usage(inner: &mut InnerState, text: &str) -> Option<glossary::GlossaryUsage> {
+    let dictionary = &inner.config.dictionary;
+    let terms: Vec<String> = dictionary
+        .pinned
+        .iter()
+        .chain(&dictionary.words)
+        .cloned()
+        .collect();
+    inner
+        .glossary_usage
+        .record(text, &terms)
+        .then(|| inner.glossary_usage.clone())
+}
 
-        if !response.status().is_success() {
-            return Err(format!(
-                "model download failed with status {}",
-                response.status()
-            ));
+/// Run Whisper on 16 kHz mono `audio` with the cached context, loading the model on first use.
+async fn transcribe_samples(
+    app: &AppHandle,
+    audio: VoicedAudio,
+    model_path: String,
+    options: TranscribeOptions,
+) -> Result<Transcript, String> {
+    let app_clone = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app_clone.state::<AppState>();
+        let ctx_lock = lock_loaded_context(&state, &model_path)?;
+
+        // Long audio is chunked; report per-chunk progress for the widget.
+        let on_progress = |done: usize, total: usize| {
+            if total > 1 {
+                let _ = app_clone.emit(
+                    "transcription_progress",
+                    TranscriptionProgressPayload { done, total },
+                );
+            }
+        };
+        whisper::transcribe_with_ctx(
+            &ctx_lock.as_ref().unwrap().0,
+            &audio.samples,
+            &audio.speech,
+            &options,
+            &on_progress,
+        )
+    })
+    .await
+    .map_err(|e| format!("transcription task failed: {e}"))?
+}
+
+/// Re-transcription of unclear dictations with a larger model (`[escalation]`).
+struct Escalation {
+    model: String,
+    model_path: String,
+    min_probability: f32,
+    cache_bytes: u64,
+}
+
+/// The `[escalation]` settings for the current recording; `None` when escalation is off or
+/// its model can't take over (it's the active model, isn't downloaded, or doesn't support
+/// the language or translation).
+fn escalation(inner: &InnerState) -> Option<Escalation> {
+    let config = &inner.config;
+    if !config.escalation.enabled {
+        return None;
+    }
+    let Some(model) = canonical_model_name(&config.escalation.model) else {
+        dlog!(
+            "pipeline: unknown escalation model '{}'",
+            config.escalation.model
+        );
+        return None;
+    };
+    if model == normalized_model_name(&config.model.name) {
+        return None;
+    }
+    if let Err(err) = check_model_language(&model, &config.general.language)
+        .and_then(|_| check_model_translate(&model, inner.translate))
+    {
+        dlog!("pipeline: not escalating to {model}: {err}");
+        return None;
+    }
+    let model_path = escalation_model_path(config);
+    if !model_path.exists() {
+        dlog!("pipeline: escalation model {model} is not downloaded");
+        return None;
+    }
+    Some(Escalation {
+        model,
+        model_path: model_path.to_str()?.to_string(),
+        min_probability: normalized_escalation_min_probability(config.escalation.min_probability),
+        cache_bytes: config.escalation.cache_mb as u64 * 1_000_000,
+    })
+}
+
+/// `transcript` again with the escalation model when its mean token probability is below
+/// the threshold. On failure the first transcript is kept.
+async fn escalate(
+    app: &AppHandle,
+    escalation: Escalation,
+    audio: VoicedAudio,
+    options: TranscribeOptions,
+    transcript: Transcript,
+) -> Transcript {
+    let Some(probability) = transcript.mean_probability() else {
+        return transcript;
+    };
+    if probability >= escalation.min_probability {
+        return transcript;
+    }
+    dlog!(
+        "pipeline: mean token probability {probability:.2} is below {:.2}, re-transcribing with {}",
+        escalation.min_probability,
+        escalation.model
+    );
+    set_widget_state(
+        app,
+        "processing",
+        Some(format!("Checking with {}...", escalation.model)),
+    );
+
+    let app_clone = app.clone();
+    let escalated = tauri::async_runtime::spawn_blocking(move || {
+        let state = app_clone.state::<AppState>();
+        let mut models = state
+            .escalation_models
+            .lock()
+            .map_err(|_| "escalation model cache lock poisoned".to_string())?;
+        models.set_capacity(escalation.cache_bytes);
+        let path = &escalation.model_path;
+        if !models.contains(path) {
+            dlog!("pipeline: loading escalation model {}", escalation.model);
+        }
+        let bytes = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let ctx = models.get_or_load(path, bytes, || {
+            whisper::load_context(path).map(SendWhisperCtx)
+        })?;
+        whisper::transcribe_with_ctx(&ctx.0, &audio.samples, &audio.speech, &options, &|_, _| {})
+    })
+    .await
+    .map_err(|e| format!("escalation task failed: {e}"))
+    .and_then(|result| result);
+
+    match escalated {
+        Ok(escalated) => {
+            dlog!(
+                "pipeline: escalation done, mean token probability {:.2}",
+                escalated.mean_probability().unwrap_or(0.0)
+            );
+            escalated
         }
+        Err(err) => {
+            dlog!("pipeline: escalation failed, keeping the first transcript: {err}");
+            transcript
+        }
+    }
+}
 
-        let total = response.content_length().unwrap_or(0);
-        let mut file = File::create(&model_path)
-            .map_err(|e| format!("failed creating model file {}: {e}", model_path.display()))?;
+/// Lock the cached Whisper context, loading the model first if it isn't loaded yet.
+/// The returned guard always holds a context.
+fn lock_loaded_context<'a>(
+    state: &'a AppState,
+    model_path: &str,
+) -> Result<MutexGuard<'a, Option<SendWhisperCtx>>, String> {
+    let mut ctx_lock = state
+        .whisper_ctx
+        .lock()
+        .map_err(|_| "whisper ctx lock poisoned".to_string())?;
+
+    if ctx_lock.is_none() {
+        dlog!("pipeline: loading whisper model (first run)");
+        let ctx = whisper::load_context(model_path)?;
+        *ctx_lock = Some(SendWhisperCtx(ctx));
+        dlog!("pipeline: whisper model loaded and cached");
+    }
+    Ok(ctx_lock)
+}
 
-        let mut downloaded: u64 = 0;
-        let mut buf = [0u8; 16 * 1024];
+/// Log the source-language text of a translated recording. Runs in the background after
+/// the English text is on its way, so translation latency isn't doubled. With live
+/// transcription this only covers the audio after the committed prefix.
+///
+/// It's only for the log, so it never queues for the Whisper context: when a dictation or
+/// live pass holds it, the text is skipped. Once running it holds the context, so a
+/// dictation stopped right after a translated one waits for it to finish.
+fn spawn_source_text_log(app: &AppHandle, audio: VoicedAudio, mut options: TranscribeOptions) {
+    options.translate = false;
+    // A live prefix is English by now, so it can't condition the source language.
+    options.context.clear();
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let Ok(ctx_lock) = state.whisper_ctx.try_lock() else {
+            dlog!("pipeline: whisper is busy, skipping the source-language text");
+            return;
+        };
+        let Some(ctx) = ctx_lock.as_ref() else {
+            return;
+        };
+        match whisper::transcribe_with_ctx(
+            &ctx.0,
+            &audio.samples,
+            &audio.speech,
+            &options,
+            &|_, _| {},
+        ) {
+            Ok(source) => dlog!("pipeline: source-language text: {}", source.text),
+            Err(err) => dlog!("pipeline: source-language transcription failed: {err}"),
+        }
+    });
+}
 
-        loop {
-            let read = response
-                .read(&mut buf)
-                .map_err(|e| format!("failed reading model stream: {e}"))?;
-            if read == 0 {
-                break;
+/// Formatter cleanup (at the `basic` level) followed by dictionary replacements.
+fn format_transcript(raw_text: &str, level: &str, replacements: &[ReplacementEntry]) -> String {
+    if level == "basic" {
+        let text = formatter::format_text(raw_text);
+        formatter::apply_replacements(&text, replacements)
+    } else {
+        let text = raw_text.trim().to_string();
+        formatter::apply_replacements(&text, replacements)
+    }
+}
+
+/// Transcribe an audio file (WAV/FLAC/MP3/OGG) through Whisper, the formatter and the
+/// dictionary. Returns the text, optionally copied to the clipboard — never pasted, and
+/// the recording status and widget are left alone.
+pub async fn transcribe_file_inner(
+    app: AppHandle,
+    path: PathBuf,
+    copy_to_clipboard: bool,
+) -> Result<String, String> {
+    let state = app.state::<AppState>();
+
+    if !state.model_ready.load(std::sync::atomic::Ordering::SeqCst) {
+        return Err("Model is still loading, please wait...".to_string());
+    }
+
+    let (mut options, formatting_level, model_path_str, dict_replacements) =
+        with_state(&state, |inner| {
+            if !WhisperEngine::new(&inner.config).model_exists() {
+                return Err("Whisper model is missing. Download model first.".to_string());
             }
+            let model = &inner.config.model.name;
+            check_model_language(model, &inner.config.general.language)?;
+            check_model_translate(model, inner.config.general.translate)?;
+            let model_path = model_file_path(&inner.config)
+                .to_str()
+                .ok_or_else(|| "invalid model path".to_string())?
+                .to_string();
+            Ok((
+                transcribe_options(inner, inner.config.general.translate),
+                inner.config.formatting.level.clone(),
+                model_path,
+                inner.config.dictionary.replacements.clone(),
+            ))
+        })?;
+    // A dictation carried over to the next recording has nothing to do with the file.
+    options.context.clear();
+
+    dlog!("pipeline: decoding {}", path.display());
+    let audio = tauri::async_runtime::spawn_blocking(move || decode::decode_to_mono_16k(&path))
+        .await
+        .map_err(|e| format!("decode task failed: {e}"))??;
+    if audio.len() < MIN_TRANSCRIBE_SAMPLES {
+        return Err("Audio file is too short to transcribe".to_string());
+    }
+
+    dlog!("pipeline: transcribing file, {} samples", audio.len());
+    // Files never went through the recorder's VAD.
+    let audio = VoicedAudio {
+        speech: hallucination::detect_speech(&audio, &options.hallucinations),
+        samples: audio,
+    };
+    let source_audio = options.translate.then(|| audio.clone());
+    let transcript = transcribe_samples(&app, audio, model_path_str, options.clone()).await?;
+    if let Some(source_audio) = source_audio {
+        spawn_source_text_log(&app, source_audio, options);
+    }
+    let formatted = format_transcript(&transcript.text, &formatting_level, &dict_replacements);
+    dlog!("pipeline: file transcription done, len={}", formatted.len());
+
+    if copy_to_clipboard && !formatted.is_empty() {
+        tauri::async_runtime::spawn_blocking({
+            let text = formatted.clone();
+            move || crate::injector::copy_text(&text)
+        })
+        .await
+        .map_err(|e| format!("clipboard task failed: {e}"))??;
+    }
 
-            file.write_all(&buf[..read])
-                .map_err(|e| format!("failed writing model file: {e}"))?;
+    Ok(formatted)
+}
 
-            downloaded += read as u64;
-            if total > 0 {
-                let progress = (downloaded as f64 / total as f64).clamp(0.0, 1.0);
-                let _ = app.emit("model_download_progress", progress);
+/// Hash the active model and compare it with its expected SHA-256: the catalog's, the one
+/// recorded at download, or the one Hugging Face reports. Stores the result for
+/// `ModelStatus::valid` and emits `model_verified`.
+pub async fn verify_model_inner(app: AppHandle) -> Result<ModelStatus, String> {
+    let state = app.state::<AppState>();
+    let (model_path, entry) = with_state(&state, |inner| {
+        Ok((
+            model_file_path(&inner.config),
+            models::catalog().resolve(&inner.config.model.name).clone(),
+        ))
+    })?;
+    if !model_path.exists() {
+        return Err("Whisper model is missing. Download model first.".to_string());
+    }
+
+    dlog!("verify: hashing {}", model_path.display());
+    let valid = tauri::async_runtime::spawn_blocking({
+        let entry = entry.clone();
+        move || -> Result<Option<bool>, String> {
+            let recorded = verify::recorded_hash(&model_path);
+            let expected = entry
+                .sha256
+                .clone()
+                .or_else(|| recorded.clone())
+                .or_else(|| download::server_sha256(&entry.url));
+            let valid = verify::verify_model_file(&model_path, expected.as_deref())?;
+            if let (Some(true), None, Some(hash)) = (valid, &recorded, &expected) {
+                // Next time, no network needed.
+                verify::record_hash(&model_path, hash)?;
             }
+            Ok(valid)
         }
-
-        let _ = app.emit("model_download_progress", 1.0_f64);
-        Ok(())
     })
     .await
-    .map_err(|e| format!("download task failed: {e}"))??;
+    .map_err(|e| format!("verify task failed: {e}"))??;
 
-    Ok(())
+    match valid {
+        Some(true) => dlog!("verify: {} is intact", entry.name),
+        Some(false) => dlog!("verify: {} is corrupted (SHA-256 mismatch)", entry.name),
+        None => dlog!("verify: no SHA-256 known for {}", entry.name),
+    }
+
+    let status = with_state(&state, |inner| {
+        // The model may have been switched while hashing.
+        if models::catalog().resolve(&inner.config.model.name).name == entry.name {
+            inner.model_valid = valid;
+        }
+        Ok(inner.model_status())
+    })?;
+    let _ = app.emit("model_verified", &status);
+    Ok(status)
+}
+
+/// Time the active model on `path` (or a synthetic clip) at several thread counts; see
+/// `benchmark`. Holds the Whisper context throughout, so a recording that ends meanwhile
+/// waits for the benchmark.
+pub async fn benchmark_model_inner(
+    app: AppHandle,
+    path: Option<PathBuf>,
+) -> Result<BenchmarkReport, String> {
+    let state = app.state::<AppState>();
+    let (mut options, model, model_path) = with_state(&state, |inner| {
+        if inner.status != AppStatus::Idle {
+            return Err("Can't benchmark while recording or transcribing".to_string());
+        }
+        if !WhisperEngine::new(&inner.config).model_exists() {
+            return Err("Whisper model is missing. Download model first.".to_string());
+        }
+        let model_path = model_file_path(&inner.config)
+            .to_str()
+            .ok_or_else(|| "invalid model path".to_string())?
+            .to_string();
+        Ok((
+            transcribe_options(inner, false),
+            inner.config.model.name.clone(),
+            model_path,
+        ))
+    })?;
+    // Detect once here rather than on every run.
+    if options.language == LANGUAGE_AUTO {
+        options.language = "en".to_string();
+    }
+    let current_threads = options.threads;
+
+    let audio = match path {
+        Some(path) => {
+            tauri::async_runtime::spawn_blocking(move || decode::decode_to_mono_16k(&path))
+                .await
+                .map_err(|e| format!("decode task failed: {e}"))??
+        }
+        None => benchmark::synthetic_clip(benchmark::SYNTHETIC_CLIP_SECS),
+    };
+    if audio.len() < MIN_TRANSCRIBE_SAMPLES {
+        return Err("Audio file is too short to benchmark".to_string());
+    }
+    let clip_secs = audio.len() as f64 / TARGET_SAMPLE_RATE as f64;
+    let thread_counts = benchmark::thread_candidates(whisper_threads(0));
+    dlog!("benchmark: {model}, {clip_secs:.1} s clip, threads {thread_counts:?}");
+
+    let app_clone = app.clone();
+    let runs = tauri::async_runtime::spawn_blocking(move || {
+        let state = app_clone.state::<AppState>();
+        let ctx_lock = lock_loaded_context(&state, &model_path)?;
+        let ctx = &ctx_lock.as_ref().unwrap().0;
+        benchmark::run(ctx, &audio, &options, &thread_counts, &|run| {
+            let _ = app_clone.emit("benchmark_progress", run);
+        })
+    })
+    .await
+    .map_err(|e| format!("benchmark task failed: {e}"))??;
+
+    let recommended_threads = benchmark::recommend(&runs);
+    dlog!("benchmark: recommended threads {recommended_threads:?}");
+    Ok(BenchmarkReport {
+        model,
+        clip_secs,
+        runs,
+        recommended_threads,
+        current_threads,
+    })
 }
@@ -0,0 +1,361 @@
+//! Streaming sample-rate conversion to Whisper's 16 kHz.
+//!
+//! Mic input arrives at 44.1/48 kHz and is converted chunk by chunk inside the capture
+//! callback, so `AudioRecorder::stop` has nothing left to resample.
+//!
+//! - [`SincResampler`] — band-limited windowed-sinc (Blackman) interpolation. Low-passes at
+//!   ~90% of the output Nyquist before decimating, so 8–24 kHz content (sibilants, fan hiss)
+//!   doesn't alias back into the speech band. Default.
+//! - [`LinearResampler`] — two-tap linear interpolation, no anti-aliasing. Cheaper; kept as
+//!   the `[audio] resampler = "linear"` fast path.
+//!
+//! Both step through the input with an exact rational position (`in_rate / out_rate`), so
+//! chunk boundaries never introduce drift: feeding a signal in pieces produces the same
+//! output as feeding it at once.
+
+use crate::config::RESAMPLER_LINEAR;
+
+/// Kernel half-width in zero crossings of the low-pass sinc. 16 gives > 60 dB stopband
+/// rejection with a Blackman window at a modest per-sample cost.
+const SINC_ZERO_CROSSINGS: usize = 16;
+
+/// Low-pass cutoff as a fraction of the lower Nyquist frequency. Leaves room for the
+/// transition band so the stopband starts at Nyquist.
+const SINC_CUTOFF: f64 = 0.9;
+
+/// Number of precomputed fractional phases. Coefficients between phases are linearly
+/// interpolated, which keeps table error far below the window's stopband.
+const SINC_PHASES: usize = 256;
+
+/// Streaming converter selected from `[audio] resampler`.
+pub enum StreamResampler {
+    Passthrough,
+    Linear(LinearResampler),
+    Sinc(SincResampler),
+}
+
+impl StreamResampler {
+    pub fn new(in_rate: u32, out_rate: u32, kind: &str) -> Self {
+        if in_rate == out_rate || in_rate == 0 || out_rate == 0 {
+            Self::Passthrough
+        } else if kind.trim().eq_ignore_ascii_case(RESAMPLER_LINEAR) {
+            Self::Linear(LinearResampler::new(in_rate, out_rate))
+        } else {
+            Self::Sinc(SincResampler::new(in_rate, out_rate))
+        }
+    }
+
+    /// Convert `input` and append the produced samples to `out`.
+    pub fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        match self {
+            Self::Passthrough => out.extend_from_slice(input),
+            Self::Linear(r) => r.process(input, out),
+            Self::Sinc(r) => r.process(input, out),
+        }
+    }
+
+    /// Emit the samples still held back for look-ahead. Call once at end of stream.
+    pub fn flush(&mut self, out: &mut Vec<f32>) {
+        match self {
+            Self::Passthrough => {}
+            Self::Linear(r) => r.flush(out),
+            Self::Sinc(r) => r.flush(out),
+        }
+    }
+}
+
+/// Exact rational read position: output sample `n` sits at input time `n * in / out`.
+struct Position {
+    /// Whole input-sample index, relative to the start of the resampler's history buffer.
+    index: usize,
+    /// Fractional part as `frac / out_rate`.
+    frac: u64,
+    in_rate: u64,
+    out_rate: u64,
+}
+
+impl Position {
+    fn new(start: usize, in_rate: u32, out_rate: u32) -> Self {
+        let divisor = gcd(in_rate as u64, out_rate as u64);
+        Self {
+            index: start,
+            frac: 0,
+            in_rate: in_rate as u64 / divisor,
+            out_rate: out_rate as u64 / divisor,
+        }
+    }
+
+    fn phase(&self) -> f64 {
+        self.frac as f64 / self.out_rate as f64
+    }
+
+    fn advance(&mut self) {
+        self.frac += self.in_rate;
+        self.index += (self.frac / self.out_rate) as usize;
+        self.frac %= self.out_rate;
+    }
+}
+
+pub struct LinearResampler {
+    history: Vec<f32>,
+    pos: Position,
+    ended: bool,
+}
+
+impl LinearResampler {
+    pub fn new(in_rate: u32, out_rate: u32) -> Self {
+        Self {
+            history: Vec::new(),
+            pos: Position::new(0, in_rate, out_rate),
+            ended: false,
+        }
+    }
+
+    pub fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        self.history.extend_from_slice(input);
+        // Need the sample after the read position before interpolating, unless flushing.
+        while self.pos.index + 1 < self.history.len()
+            || (self.ended && self.pos.index < self.history.len())
+        {
+            let a = self.history[self.pos.index];
+            let b = self.history.get(self.pos.index + 1).copied().unwrap_or(a);
+            out.push(a + (b - a) * self.pos.phase() as f32);
+            self.pos.advance();
+        }
+
+        let consumed = self.pos.index.min(self.history.len());
+        self.history.drain(..consumed);
+        self.pos.index -= consumed;
+    }
+
+    pub fn flush(&mut self, out: &mut Vec<f32>) {
+        self.ended = true;
+        self.process(&[], out);
+    }
+}
+
+pub struct SincResampler {
+    /// `SINC_PHASES + 1` rows of `2 * half` taps; row `p` is the kernel for phase `p / SINC_PHASES`.
+    table: Vec<f32>,
+    half: usize,
+    /// Input history, starting `half - 1` samples before the read position.
+    history: Vec<f32>,
+    pos: Position,
+}
+
+impl SincResampler {
+    pub fn new(in_rate: u32, out_rate: u32) -> Self {
+        // Cutoff relative to the input Nyquist: only band-limit below the lower of the two rates.
+        let cutoff = SINC_CUTOFF * (out_rate as f64 / in_rate as f64).min(1.0);
+        let half = (SINC_ZERO_CROSSINGS as f64 / cutoff).ceil() as usize;
+        let taps = 2 * half;
+
+        let mut table = Vec::with_capacity((SINC_PHASES + 1) * taps);
+        for p in 0..=SINC_PHASES {
+            let phase = p as f64 / SINC_PHASES as f64;
+            for t in 0..taps {
+                // Tap t reads input sample `index - half + 1 + t`; distance from read position:
+                let distance = phase + (half - 1) as f64 - t as f64;
+                table.push(windowed_sinc(distance, cutoff, half as f64) as f32);
+            }
+        }
+
+        Self {
+            table,
+            half,
+            // Left padding so the first output is centred on input sample 0.
+            history: vec![0.0; half - 1],
+            pos: Position::new(half - 1, in_rate, out_rate),
+        }
+    }
+
+    pub fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        self.history.extend_from_slice(input);
+
+        // Each output needs `half` samples of right context past the read position.
+        while self.pos.index + self.half < self.history.len() {
+            out.push(self.sample_at_position());
+            self.pos.advance();
+        }
+
+        // Keep `half - 1` samples of left context for the next read position.
+        let consumed = (self.pos.index + 1)
+            .saturating_sub(self.half)
+            .min(self.history.len());
+        self.history.drain(..consumed);
+        self.pos.index -= consumed;
+    }
+
+    pub fn flush(&mut self, out: &mut Vec<f32>) {
+        // Zero-pad the right context so every read position inside the real signal resolves.
+        let end = self.history.len();
+        self.history.resize(end + self.half, 0.0);
+        while self.pos.index < end {
+            out.push(self.sample_at_position());
+            self.pos.advance();
+        }
+
+        self.history.clear();
+        self.history.resize(self.half - 1, 0.0);
+        self.pos.index = self.half - 1;
+        self.pos.frac = 0;
+    }
+
+    fn sample_at_position(&self) -> f32 {
+        let taps = 2 * self.half;
+        let start = self.pos.index + 1 - self.half;
+        let window = &self.history[start..start + taps];
+
+        let scaled = self.pos.phase() * SINC_PHASES as f64;
+        let p = (scaled as usize).min(SINC_PHASES - 1);
+        let blend = (scaled - p as f64) as f32;
+        let lo = &self.table[p * taps..(p + 1) * taps];
+        let hi = &self.table[(p + 1) * taps..(p + 2) * taps];
+
+        let mut acc_lo = 0.0f32;
+        let mut acc_hi = 0.0f32;
+        for ((x, l), h) in window.iter().zip(lo).zip(hi) {
+            acc_lo += x * l;
+            acc_hi += x * h;
+        }
+        acc_lo + (acc_hi - acc_lo) * blend
+    }
+}
+
+fn windowed_sinc(distance: f64, cutoff: f64, half: f64) -> f64 {
+    let u = distance / half;
+    if u.abs() >= 1.0 {
+        return 0.0;
+    }
+    let x = std::f64::consts::PI * cutoff * distance;
+    let sinc = if x.abs() < 1e-12 { 1.0 } else { x.sin() / x };
+    let window = 0.42
+        + 0.5 * (std::f64::consts::PI * u).cos()
+        + 0.08 * (2.0 * std::f64::consts::PI * u).cos();
+    cutoff * sinc * window
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a.max(1)
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::resample_linear;
+    use crate::config::RESAMPLER_SINC;
+
+    fn sine(freq: f32, rate: u32, seconds: f32) -> Vec<f32> {
+        let len = (rate as f32 * seconds) as usize;
+        (0..len)
+            .map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32).sin() * 0.5)
+            .collect()
+    }
+
+    fn run(input: &[f32], in_rate: u32, out_rate: u32, kind: &str, chunk: usize) -> Vec<f32> {
+        let mut resampler = StreamResampler::new(in_rate, out_rate, kind);
+        let mut out = Vec::new();
+        for part in input.chunks(chunk) {
+            resampler.process(part, &mut out);
+        }
+        resampler.flush(&mut out);
+        out
+    }
+
+    /// RMS over the middle of the buffer, skipping filter warm-up at both ends.
+    fn steady_rms(samples: &[f32]) -> f32 {
+        let skip = samples.len() / 10;
+        let body = &samples[skip..samples.len() - skip];
+        let sum_sq: f32 = body.iter().map(|s| s * s).sum();
+        (sum_sq / body.len() as f32).sqrt()
+    }
+
+    /// Gain in dB of a 0.5-amplitude sine after resampling.
+    fn gain_db(output: &[f32]) -> f32 {
+        let reference = 0.5 / std::f32::consts::SQRT_2;
+        20.0 * (steady_rms(output) / reference).log10()
+    }
+
+    #[test]
+    fn passthrough_when_rates_match() {
+        let input = sine(440.0, 16_000, 0.1);
+        assert_eq!(run(&input, 16_000, 16_000, RESAMPLER_SINC, 128), input);
+    }
+
+    #[test]
+    fn sinc_output_length_matches_ratio() {
+        for in_rate in [48_000u32, 44_100, 22_050, 8_000] {
+            let input = sine(440.0, in_rate, 1.0);
+            let out = run(&input, in_rate, 16_000, RESAMPLER_SINC, 512);
+            let expected = input.len() as u64 * 16_000 / in_rate as u64;
+            assert!(
+                (out.len() as i64 - expected as i64).abs() <= 1,
+                "{in_rate}: got {} expected {expected}",
+                out.len()
+            );
+        }
+    }
+
+    #[test]
+    fn chunking_does_not_change_sinc_output() {
+        let input = sine(1_000.0, 44_100, 0.5);
+        let whole = run(&input, 44_100, 16_000, RESAMPLER_SINC, input.len());
+        let pieces = run(&input, 44_100, 16_000, RESAMPLER_SINC, 37);
+        assert_eq!(whole.len(), pieces.len());
+        for (a, b) in whole.iter().zip(&pieces) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn streaming_linear_matches_one_shot_linear() {
+        let input = sine(700.0, 48_000, 0.25);
+        let streamed = run(&input, 48_000, 16_000, RESAMPLER_LINEAR, 100);
+        let one_shot = resample_linear(&input, 48_000, 16_000);
+        assert_eq!(streamed, one_shot);
+    }
+
+    #[test]
+    fn sinc_passband_is_flat_for_speech() {
+        for in_rate in [48_000u32, 44_100] {
+            for freq in [300.0f32, 1_000.0, 3_000.0, 6_000.0] {
+                let out = run(
+                    &sine(freq, in_rate, 0.5),
+                    in_rate,
+                    16_000,
+                    RESAMPLER_SINC,
+                    480,
+                );
+                let gain = gain_db(&out);
+                assert!(
+                    gain.abs() < 0.5,
+                    "{in_rate} Hz, {freq} Hz tone: {gain:.2} dB"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sinc_rejects_content_above_nyquist_where_linear_aliases() {
+        // Sibilant-band tones that fold back into 0–8 kHz when decimated without a filter.
+        for in_rate in [48_000u32, 44_100] {
+            for freq in [10_000.0f32, 12_000.0, 15_000.0] {
+                let input = sine(freq, in_rate, 0.5);
+                let linear = resample_linear(&input, in_rate, 16_000);
+                let sinc = run(&input, in_rate, 16_000, RESAMPLER_SINC, 480);
+
+                let linear_db = gain_db(&linear);
+                let sinc_db = gain_db(&sinc);
+                assert!(
+                    linear_db > -20.0,
+                    "{in_rate}/{freq}: linear {linear_db:.1} dB"
+                );
+                assert!(sinc_db < -50.0, "{in_rate}/{freq}: sinc {sinc_db:.1} dB");
+            }
+        }
+    }
+}
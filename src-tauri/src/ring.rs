@@ -0,0 +1,168 @@
+//! Lock-free single-producer / single-consumer sample ring.
+//!
+//! Sits between the cpal callback (real-time audio thread, producer) and the recorder's
+//! consumer thread. The producer never blocks or allocates: when the ring is full, the
+//! samples that don't fit are dropped and counted as overruns.
+//!
+//! Slots are `AtomicU32` holding `f32` bits, so the ring needs no `unsafe`. Relaxed slot
+//! accesses are ordered by the Release/Acquire pair on `head` (written by the producer)
+//! and `tail` (written by the consumer).
+
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct Shared {
+    slots: Box<[AtomicU32]>,
+    /// Total samples ever written. Only the producer stores to it.
+    head: AtomicUsize,
+    /// Total samples ever read. Only the consumer stores to it.
+    tail: AtomicUsize,
+    /// Samples dropped because the ring was full.
+    overruns: AtomicU64,
+}
+
+/// Write half. Owned by the audio callback; not `Clone`, so there is only ever one.
+pub struct RingProducer {
+    shared: Arc<Shared>,
+}
+
+/// Read half. Owned by the consumer thread.
+pub struct RingConsumer {
+    shared: Arc<Shared>,
+}
+
+/// Create a ring holding up to `capacity` samples.
+pub fn sample_ring(capacity: usize) -> (RingProducer, RingConsumer) {
+    let slots = (0..capacity.max(1)).map(|_| AtomicU32::new(0)).collect();
+    let shared = Arc::new(Shared {
+        slots,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        overruns: AtomicU64::new(0),
+    });
+    (
+        RingProducer {
+            shared: Arc::clone(&shared),
+        },
+        RingConsumer { shared },
+    )
+}
+
+impl RingProducer {
+    /// Append as many samples as fit; the rest are dropped and counted as overruns.
+    /// Returns the number written.
+    pub fn push_slice(&self, data: &[f32]) -> usize {
+        let shared = &*self.shared;
+        let capacity = shared.slots.len();
+        let head = shared.head.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::Acquire);
+        let free = capacity - (head - tail);
+
+        let count = data.len().min(free);
+        for (i, sample) in data[..count].iter().enumerate() {
+            shared.slots[(head + i) % capacity].store(sample.to_bits(), Ordering::Relaxed);
+        }
+        shared.head.store(head + count, Ordering::Release);
+
+        let dropped = data.len() - count;
+        if dropped > 0 {
+            shared.overruns.fetch_add(dropped as u64, Ordering::Relaxed);
+        }
+        count
+    }
+}
+
+impl RingConsumer {
+    /// Move every available sample into `out` (appending). Returns the number read.
+    pub fn pop_into(&self, out: &mut Vec<f32>) -> usize {
+        let shared = &*self.shared;
+        let capacity = shared.slots.len();
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let head = shared.head.load(Ordering::Acquire);
+
+        let count = head - tail;
+        out.reserve(count);
+        for i in 0..count {
+            let bits = shared.slots[(tail + i) % capacity].load(Ordering::Relaxed);
+            out.push(f32::from_bits(bits));
+        }
+        shared.tail.store(tail + count, Ordering::Release);
+        count
+    }
+
+    /// Total samples dropped by the producer so far.
+    pub fn overruns(&self) -> u64 {
+        self.shared.overruns.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_samples_in_order() {
+        let (producer, consumer) = sample_ring(8);
+        assert_eq!(producer.push_slice(&[0.1, 0.2, 0.3]), 3);
+
+        let mut out = Vec::new();
+        assert_eq!(consumer.pop_into(&mut out), 3);
+        assert_eq!(out, vec![0.1, 0.2, 0.3]);
+        assert_eq!(consumer.pop_into(&mut out), 0);
+    }
+
+    #[test]
+    fn wraps_around_capacity() {
+        let (producer, consumer) = sample_ring(4);
+        let mut out = Vec::new();
+        for round in 0..10 {
+            let base = round as f32;
+            producer.push_slice(&[base, base + 0.5, base + 0.25]);
+            out.clear();
+            consumer.pop_into(&mut out);
+            assert_eq!(out, vec![base, base + 0.5, base + 0.25]);
+        }
+        assert_eq!(consumer.overruns(), 0);
+    }
+
+    #[test]
+    fn counts_overruns_when_full() {
+        let (producer, consumer) = sample_ring(4);
+        assert_eq!(producer.push_slice(&[1.0; 3]), 3);
+        assert_eq!(producer.push_slice(&[2.0; 3]), 1);
+        assert_eq!(consumer.overruns(), 2);
+
+        let mut out = Vec::new();
+        consumer.pop_into(&mut out);
+        assert_eq!(out, vec![1.0, 1.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn concurrent_producer_and_consumer_preserve_order() {
+        let (producer, consumer) = sample_ring(1024);
+        let total = 200_000usize;
+
+        let writer = std::thread::spawn(move || {
+            let mut next = 0usize;
+            while next < total {
+                let end = (next + 64).min(total);
+                let chunk: Vec<f32> = (next..end).map(|v| v as f32).collect();
+                let written = producer.push_slice(&chunk);
+                next += written;
+                if written < chunk.len() {
+                    std::thread::yield_now();
+                }
+            }
+        });
+
+        let mut out = Vec::with_capacity(total);
+        while out.len() < total {
+            if consumer.pop_into(&mut out) == 0 {
+                std::thread::yield_now();
+            }
+        }
+        writer.join().unwrap();
+
+        assert!(out.iter().enumerate().all(|(i, v)| *v == i as f32));
+    }
+}
@@ -6,7 +6,12 @@
 //! shouldn't block state reads.
 
 use crate::audio::AudioRecorder;
+use crate::carryover::LastDictation;
 use crate::config::AppConfig;
+use crate::glossary::GlossaryUsage;
+use crate::live::LiveSession;
+use crate::model_cache::ModelCache;
+use crate::whisper::{DetectedLanguage, WhisperEngine};
 use serde::Serialize;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::AtomicBool;
@@ -38,24 +43,108 @@ impl AppStatus {
 pub struct StatusPayload {
     pub status: String,
     pub message: Option<String>,
+    /// Language detected for the last transcription when `language = "auto"`.
+    pub detected_language: Option<DetectedLanguage>,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ModelStatus {
     pub exists: bool,
     pub path: String,
+    /// Result of the last SHA-256 check; `None` until checked or when there's no hash to
+    /// check against.
+    pub valid: Option<bool>,
+}
+
+/// A catalog model as reported by `list_models`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelInfo {
+    pub name: String,
+    pub filename: String,
+    pub size_mb: u64,
+    pub multilingual: bool,
+    pub quantization: Option<String>,
+    pub installed: bool,
+    /// Size of the installed file; 0 when not installed.
+    pub disk_bytes: u64,
+    pub active: bool,
+}
+
+/// Payload of the `audio_device_changed` event emitted by the device watcher.
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioDeviceChangedPayload {
+    pub devices: Vec<String>,
+    pub active: Option<String>,
+    /// True when a running recording was moved to another device.
+    pub failed_over: bool,
+}
+
+/// Payload of `transcription_progress`, emitted after each chunk of a long transcription.
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionProgressPayload {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// Payload of `model_download_progress`, emitted a few times per second while downloading.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModelDownloadProgressPayload {
+    /// 0.0–1.0; stays 0 while the size is unknown.
+    pub progress: f64,
+    pub downloaded_bytes: u64,
+    /// 0 when the server didn't report a size.
+    pub total_bytes: u64,
+    pub bytes_per_sec: f64,
+    pub eta_secs: Option<f64>,
+}
+
+/// One thread count measured by `benchmark_model`; also the `benchmark_progress` payload.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkRun {
+    pub threads: usize,
+    pub elapsed_ms: u64,
+    /// Processing time / audio length; below 1.0 is faster than real time.
+    pub real_time_factor: f64,
+}
+
+/// Result of `benchmark_model`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkReport {
+    pub model: String,
+    pub clip_secs: f64,
+    pub runs: Vec<BenchmarkRun>,
+    /// Fewest threads about as fast as the fastest run.
+    pub recommended_threads: Option<usize>,
+    /// Threads transcription uses now.
+    pub current_threads: usize,
+}
+
+/// Payload of `partial_transcript`, emitted after each live transcription pass.
+#[derive(Debug, Clone, Serialize)]
+pub struct PartialTranscriptPayload {
+    /// Text that will not change any more.
+    pub committed: String,
+    /// The latest guess for the audio after `committed`; may still be revised.
+    pub tentative: String,
 }
 
 /// Wrapper to make WhisperContext movable across thread boundaries.
-/// Safety: WhisperContext is only accessed while holding the whisper_ctx Mutex,
-/// guaranteeing exclusive single-threaded access at all times.
+/// Safety: WhisperContext is only accessed while holding the Mutex it's stored behind
+/// (`whisper_ctx` or `escalation_models`), guaranteeing exclusive single-threaded access
+/// at all times.
 pub struct SendWhisperCtx(pub WhisperContext);
 unsafe impl Send for SendWhisperCtx {}
 
 pub struct AppState {
     pub inner_state: Mutex<InnerState>,
     pub whisper_ctx: Mutex<Option<SendWhisperCtx>>,
+    /// Models loaded for `[escalation]`, besides the one in `whisper_ctx`.
+    pub escalation_models: Mutex<ModelCache<SendWhisperCtx>>,
     pub model_ready: Arc<AtomicBool>,
+    /// A model download is running; only one runs at a time.
+    pub model_downloading: AtomicBool,
+    /// Set by `cancel_model_download` to stop the running download.
+    pub download_cancel: Arc<AtomicBool>,
 }
 
 pub struct InnerState {
@@ -68,14 +157,40 @@ pub struct InnerState {
     /// PID of the app that was frontmost when recording started.
     /// Used to restore focus before pasting so Cmd+V reaches the right app.
     pub previous_app_pid: Option<i32>,
+    /// Live transcription thread of the current recording, if enabled.
+    pub live_session: Option<LiveSession>,
+    /// The current recording is translated to English (config or translate hotkey).
+    pub translate: bool,
+    /// Integrity of the active model's file (see [`ModelStatus::valid`]).
+    pub model_valid: Option<bool>,
+    /// How often dictionary terms were dictated; ranks the glossary.
+    pub glossary_usage: GlossaryUsage,
+    /// The previous dictation, for `general.carry_context`.
+    pub last_dictation: Option<LastDictation>,
+    /// Text carried over from `last_dictation` into the current recording; empty for none.
+    pub carried_context: String,
 }
 
 impl InnerState {
+    pub fn model_status(&self) -> ModelStatus {
+        let engine = WhisperEngine::new(&self.config);
+        ModelStatus {
+            exists: engine.model_exists(),
+            path: engine.model_path().display().to_string(),
+            valid: self.model_valid,
+        }
+    }
+
     pub fn reset_to_idle(&mut self) {
+        if let Some(live) = self.live_session.take() {
+            live.cancel();
+        }
         self.status = AppStatus::Idle;
+        self.translate = false;
         self.toggle_active = false;
         self.press_instant = None;
         self.previous_app_pid = None;
+        self.carried_context.clear();
     }
 }
 
@@ -83,19 +198,30 @@ impl AppState {
     pub fn new(config: AppConfig) -> Self {
         let mut config = config;
         config.general.mode = crate::sanitize_recording_mode(&config.general.mode);
+        let recorder = AudioRecorder::new(&config.audio);
+        let escalation_cache_bytes = config.escalation.cache_mb as u64 * 1_000_000;
 
         Self {
             inner_state: Mutex::new(InnerState {
                 status: AppStatus::Idle,
-                recorder: AudioRecorder::new(),
+                recorder,
                 config,
                 toggle_shortcut_held: false,
                 press_instant: None,
                 toggle_active: false,
                 previous_app_pid: None,
+                live_session: None,
+                translate: false,
+                model_valid: None,
+                glossary_usage: GlossaryUsage::load(),
+                last_dictation: None,
+                carried_context: String::new(),
             }),
             whisper_ctx: Mutex::new(None),
+            escalation_models: Mutex::new(ModelCache::new(escalation_cache_bytes)),
             model_ready: Arc::new(AtomicBool::new(true)),
+            model_downloading: AtomicBool::new(false),
+            download_cancel: Arc::new(AtomicBool::new(false)),
         }
     }
 }
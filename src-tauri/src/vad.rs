@@ -0,0 +1,485 @@
+//! Streaming voice activity detection.
+//!
+//! [`VoiceActivityDetector`] is fed mono chunks from the capture callback and classifies
+//! fixed 30 ms frames as speech or non-speech against an adaptive noise floor. It keeps the
+//! speech segment boundaries (in input sample indices) so that, when recording stops,
+//! [`extract_voiced`] can hand Whisper only the voiced regions with long pauses collapsed.
+//!
+//! Why adaptive: a fixed RMS threshold drops quiet speakers in quiet rooms and lets keyboard
+//! clatter through in loud ones. Here a frame is voiced when it is well above the running
+//! noise floor, and a segment only opens after a few consecutive voiced frames, so short
+//! transients (key clicks, desk taps) never become speech. The floor is seeded from the
+//! quietest of the first frames, so a fan or an open office is learned from the start of
+//! the recording instead of over several seconds.
+
+/// Analysis frame length. 30 ms is the usual VAD granularity — short enough to find word
+/// boundaries, long enough for a stable RMS estimate.
+const FRAME_MS: u32 = 30;
+
+/// A frame is voiced when its RMS exceeds the noise floor by this factor (~10 dB).
+const SPEECH_RATIO: f32 = 3.0;
+
+/// Absolute RMS floor so digital silence / a muted mic never counts as speech.
+const MIN_SPEECH_RMS: f32 = 0.002;
+
+/// The first frames (~200 ms) seed the noise floor with their minimum level before they
+/// are classified.
+const CALIBRATION_FRAMES: usize = 7;
+
+/// Cap on the seeded floor in case the user was already talking at the start.
+const MAX_INITIAL_NOISE_FLOOR: f32 = 0.02;
+
+/// Noise floor adaptation rates per frame: fall quickly when the room gets quieter,
+/// rise slowly on non-speech frames, and barely move during speech.
+const NOISE_FALL_RATE: f32 = 0.2;
+const NOISE_RISE_RATE: f32 = 0.05;
+const NOISE_SPEECH_RATE: f32 = 0.001;
+
+/// Consecutive voiced frames required to open a segment (~90 ms). Rejects key clicks.
+const ONSET_FRAMES: usize = 3;
+
+/// Non-speech frames tolerated inside a segment before it closes (~300 ms).
+const HANGOVER_FRAMES: usize = 10;
+
+/// Segments shorter than this are discarded as transients.
+const MIN_SEGMENT_MS: u32 = 150;
+
+/// Audio kept before the first voiced segment so soft onsets aren't clipped.
+const LEAD_PADDING_MS: u32 = 150;
+
+/// Audio kept after the last voiced segment so trailing words aren't clipped.
+const TAIL_PADDING_MS: u32 = 500;
+
+/// Internal pauses longer than this are collapsed to this length (half kept on each side).
+const MAX_PAUSE_MS: u32 = 400;
+
+/// A voiced region, as `[start, end)` sample indices into the recorded buffer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeechSegment {
+    pub start: usize,
+    pub end: usize,
+}
+
+pub struct VoiceActivityDetector {
+    sample_rate: u32,
+    frame_len: usize,
+    frame: Vec<f32>,
+    /// Samples consumed into completed frames.
+    position: usize,
+    noise_floor: f32,
+    /// Levels and lengths of the first frames, held back until the floor is seeded.
+    calibration: Option<Vec<(f32, usize)>>,
+    voiced_run: usize,
+    silent_run: usize,
+    current_start: Option<usize>,
+    last_voiced_end: usize,
+    segments: Vec<SpeechSegment>,
+}
+
+impl VoiceActivityDetector {
+    pub fn new(sample_rate: u32) -> Self {
+        let frame_len = ms_to_samples(FRAME_MS, sample_rate).max(1);
+        Self {
+            sample_rate,
+            frame_len,
+            frame: Vec::with_capacity(frame_len),
+            position: 0,
+            noise_floor: MAX_INITIAL_NOISE_FLOOR,
+            calibration: Some(Vec::with_capacity(CALIBRATION_FRAMES)),
+            voiced_run: 0,
+            silent_run: 0,
+            current_start: None,
+            last_voiced_end: 0,
+            segments: Vec::new(),
+        }
+    }
+
+    /// Feed the next chunk of mono samples. Chunks may be any length.
+    pub fn process(&mut self, samples: &[f32]) {
+        let mut rest = samples;
+        while !rest.is_empty() {
+            let take = (self.frame_len - self.frame.len()).min(rest.len());
+            self.frame.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+
+            if self.frame.len() == self.frame_len {
+                let level = rms(&self.frame);
+                self.frame.clear();
+                self.process_frame(level);
+            }
+        }
+    }
+
+    /// Flush the partial frame, close any open segment, and return all speech segments.
+    pub fn finish(&mut self) -> Vec<SpeechSegment> {
+        if !self.frame.is_empty() {
+            let level = rms(&self.frame);
+            let partial = self.frame.len();
+            self.frame.clear();
+            self.process_frame_of(level, partial);
+        }
+        self.end_calibration();
+        if let Some(start) = self.current_start.take() {
+            self.close_segment(start, self.last_voiced_end);
+        }
+        self.segments.clone()
+    }
+
+    /// Speech segments found so far, including one still open, without finishing.
+    pub fn segments_so_far(&self) -> Vec<SpeechSegment> {
+        let mut segments = self.segments.clone();
+        if let Some(start) = self.current_start {
+            segments.push(SpeechSegment {
+                start,
+                end: self.last_voiced_end,
+            });
+        }
+        segments
+    }
+
+    fn threshold(&self) -> f32 {
+        (self.noise_floor * SPEECH_RATIO).max(MIN_SPEECH_RMS)
+    }
+
+    fn process_frame(&mut self, level: f32) {
+        self.process_frame_of(level, self.frame_len);
+    }
+
+    fn process_frame_of(&mut self, level: f32, len: usize) {
+        if let Some(levels) = &mut self.calibration {
+            levels.push((level, len));
+            if levels.len() >= CALIBRATION_FRAMES {
+                self.end_calibration();
+            }
+            return;
+        }
+        self.classify_frame(level, len);
+    }
+
+    /// Seed the noise floor from the held-back frames, then classify them.
+    fn end_calibration(&mut self) {
+        let Some(levels) = self.calibration.take() else {
+            return;
+        };
+        self.noise_floor = levels
+            .iter()
+            .map(|&(level, _)| level)
+            .fold(MAX_INITIAL_NOISE_FLOOR, f32::min);
+        for (level, len) in levels {
+            self.classify_frame(level, len);
+        }
+    }
+
+    fn classify_frame(&mut self, level: f32, len: usize) {
+        let frame_end = self.position + len;
+        self.position = frame_end;
+
+        if level > self.threshold() {
+            self.voiced_run += 1;
+            self.silent_run = 0;
+            self.noise_floor += (level - self.noise_floor) * NOISE_SPEECH_RATE;
+
+            if self.current_start.is_none() && self.voiced_run >= ONSET_FRAMES {
+                // Segment starts at the first frame of the onset run.
+                let run_len = (self.voiced_run - 1) * self.frame_len + len;
+                self.current_start = Some(frame_end.saturating_sub(run_len));
+            }
+            if self.current_start.is_some() {
+                self.last_voiced_end = frame_end;
+            }
+        } else {
+            self.voiced_run = 0;
+            self.silent_run += 1;
+
+            let rate = if level < self.noise_floor {
+                NOISE_FALL_RATE
+            } else {
+                NOISE_RISE_RATE
+            };
+            self.noise_floor += (level - self.noise_floor) * rate;
+
+            if self.silent_run > HANGOVER_FRAMES {
+                if let Some(start) = self.current_start.take() {
+                    self.close_segment(start, self.last_voiced_end);
+                }
+            }
+        }
+    }
+
+    fn close_segment(&mut self, start: usize, end: usize) {
+        if end > start && end - start >= ms_to_samples(MIN_SEGMENT_MS, self.sample_rate) {
+            self.segments.push(SpeechSegment { start, end });
+        }
+    }
+}
+
+/// Cut the voiced regions out of `samples` and join them.
+///
+/// Keeps [`LEAD_PADDING_MS`] before the first segment and [`TAIL_PADDING_MS`] after the last.
+/// Pauses between segments longer than [`MAX_PAUSE_MS`] are collapsed to that length, keeping
+/// the audio right after the previous segment and right before the next one.
+/// Returns an empty buffer when no speech was detected.
+pub fn extract_voiced(samples: &[f32], segments: &[SpeechSegment], sample_rate: u32) -> Vec<f32> {
+    let ranges = kept_ranges(samples.len(), segments, sample_rate);
+    let mut out = Vec::with_capacity(ranges.iter().map(|(start, end)| end - start).sum());
+    for (start, end) in ranges {
+        out.extend_from_slice(&samples[start..end]);
+    }
+    out
+}
+
+/// `segments` as sample indices into what [`extract_voiced`] returns for a buffer of `len`
+/// samples, so the speech can be located in the extracted audio.
+pub fn voiced_segments(
+    len: usize,
+    segments: &[SpeechSegment],
+    sample_rate: u32,
+) -> Vec<SpeechSegment> {
+    let mut out = Vec::with_capacity(segments.len());
+    let mut extracted = 0;
+    for (start, end) in kept_ranges(len, segments, sample_rate) {
+        // Collapsed pauses lie between segments, so each segment sits inside one range.
+        out.extend(
+            segments
+                .iter()
+                .filter(|s| s.start < end && s.end > start)
+                .map(|s| SpeechSegment {
+                    start: extracted + s.start.max(start) - start,
+                    end: extracted + s.end.min(end) - start,
+                }),
+        );
+        extracted += end - start;
+    }
+    out
+}
+
+/// The `[start, end)` ranges of a `len`-sample buffer that [`extract_voiced`] keeps.
+fn kept_ranges(len: usize, segments: &[SpeechSegment], sample_rate: u32) -> Vec<(usize, usize)> {
+    let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+        return Vec::new();
+    };
+
+    let lead = ms_to_samples(LEAD_PADDING_MS, sample_rate);
+    let tail = ms_to_samples(TAIL_PADDING_MS, sample_rate);
+    let max_pause = ms_to_samples(MAX_PAUSE_MS, sample_rate);
+
+    let start = first.start.saturating_sub(lead).min(len);
+    let end = (last.end + tail).min(len);
+    if start >= end {
+        return Vec::new();
+    }
+
+    let mut ranges = Vec::new();
+    let mut cursor = start;
+    for pair in segments.windows(2) {
+        let (prev_end, next_start) = (pair[0].end.min(len), pair[1].start.min(len));
+        if next_start <= prev_end || next_start - prev_end <= max_pause {
+            continue;
+        }
+        let keep_after = max_pause / 2;
+        ranges.push((cursor, prev_end + keep_after));
+        cursor = next_start - (max_pause - keep_after);
+    }
+    ranges.push((cursor, end));
+    ranges
+}
+
+/// `segments` relative to `offset`, for extracting only `samples[offset..]`: segments that
+/// end before `offset` are dropped and one spanning it is clipped.
+pub fn segments_after(segments: &[SpeechSegment], offset: usize) -> Vec<SpeechSegment> {
+    segments
+        .iter()
+        .filter(|s| s.end > offset)
+        .map(|s| SpeechSegment {
+            start: s.start.saturating_sub(offset),
+            end: s.end - offset,
+        })
+        .collect()
+}
+
+fn ms_to_samples(ms: u32, sample_rate: u32) -> usize {
+    (sample_rate as u64 * ms as u64 / 1000) as usize
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum_sq: f32 = samples.iter().map(|s| s * s).sum();
+    (sum_sq / samples.len() as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    /// Deterministic low-level noise (xorshift) so tests don't depend on a rand crate.
+    fn noise(len: usize, amplitude: f32, seed: u32) -> Vec<f32> {
+        let mut state = seed.max(1);
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                ((state as f32 / u32::MAX as f32) * 2.0 - 1.0) * amplitude
+            })
+            .collect()
+    }
+
+    fn tone(len: usize, amplitude: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| {
+                (i as f32 * 2.0 * std::f32::consts::PI * 220.0 / RATE as f32).sin() * amplitude
+            })
+            .collect()
+    }
+
+    fn run(samples: &[f32], chunk: usize) -> Vec<SpeechSegment> {
+        let mut vad = VoiceActivityDetector::new(RATE);
+        for part in samples.chunks(chunk) {
+            vad.process(part);
+        }
+        vad.finish()
+    }
+
+    #[test]
+    fn all_silent_has_no_segments() {
+        let samples = vec![0.0f32; RATE as usize * 2];
+        let segments = run(&samples, 512);
+        assert!(segments.is_empty());
+        assert!(extract_voiced(&samples, &segments, RATE).is_empty());
+    }
+
+    #[test]
+    fn detects_quiet_speaker_over_quiet_room() {
+        // Speech at 0.01 RMS-ish would fail a fixed 0.01 threshold; the adaptive floor catches it.
+        let mut samples = noise(RATE as usize, 0.0005, 7);
+        samples.extend(tone(RATE as usize, 0.012));
+        samples.extend(noise(RATE as usize, 0.0005, 11));
+
+        let segments = run(&samples, 441);
+        assert_eq!(segments.len(), 1);
+        let seg = segments[0];
+        let one_sec = RATE as usize;
+        assert!(seg.start.abs_diff(one_sec) <= ms_to_samples(FRAME_MS, RATE));
+        assert!(seg.end.abs_diff(2 * one_sec) <= ms_to_samples(FRAME_MS, RATE));
+    }
+
+    #[test]
+    fn trims_steady_background_noise() {
+        // A fan at ~0.015 RMS is above a fixed quiet-room threshold; the seeded floor learns it.
+        let one_sec = RATE as usize;
+        let mut samples = noise(one_sec, 0.025, 13);
+        let speech: Vec<f32> = tone(one_sec, 0.2)
+            .iter()
+            .zip(noise(one_sec, 0.025, 17))
+            .map(|(t, n)| t + n)
+            .collect();
+        samples.extend(speech);
+        samples.extend(noise(one_sec, 0.025, 19));
+
+        let segments = run(&samples, 512);
+        assert_eq!(segments.len(), 1);
+        let seg = segments[0];
+        assert!(seg.start.abs_diff(one_sec) <= ms_to_samples(FRAME_MS, RATE));
+        assert!(seg.end.abs_diff(2 * one_sec) <= ms_to_samples(FRAME_MS, RATE));
+    }
+
+    #[test]
+    fn ignores_short_clicks() {
+        let mut samples = noise(RATE as usize, 0.001, 3);
+        // 20 ms key click
+        for s in samples.iter_mut().skip(8000).take(320) {
+            *s = 0.6;
+        }
+        assert!(run(&samples, 256).is_empty());
+    }
+
+    #[test]
+    fn chunk_size_does_not_change_result() {
+        let mut samples = noise(8000, 0.001, 5);
+        samples.extend(tone(12_000, 0.2));
+        samples.extend(noise(8000, 0.001, 9));
+        assert_eq!(run(&samples, 100), run(&samples, 4096));
+    }
+
+    #[test]
+    fn collapses_long_internal_pause() {
+        let one_sec = RATE as usize;
+        let mut samples = tone(one_sec, 0.2);
+        samples.extend(vec![0.0f32; 3 * one_sec]);
+        samples.extend(tone(one_sec, 0.2));
+
+        let segments = run(&samples, 512);
+        assert_eq!(segments.len(), 2);
+
+        let voiced = extract_voiced(&samples, &segments, RATE);
+        let max_pause = ms_to_samples(MAX_PAUSE_MS, RATE);
+        let tail = ms_to_samples(TAIL_PADDING_MS, RATE);
+        // Two seconds of speech, one collapsed pause, trailing padding clamped to the buffer.
+        assert!(voiced.len() <= 2 * one_sec + max_pause + tail);
+        assert!(voiced.len() >= 2 * one_sec);
+    }
+
+    #[test]
+    fn keeps_short_internal_pause_intact() {
+        let mut samples = tone(8000, 0.2);
+        samples.extend(vec![0.0f32; 4000]); // 250 ms pause stays as-is
+        samples.extend(tone(8000, 0.2));
+        samples.extend(vec![0.0f32; 16_000]);
+
+        let segments = run(&samples, 512);
+        let voiced = extract_voiced(&samples, &segments, RATE);
+        assert_eq!(&voiced[..20_000], &samples[..20_000]);
+    }
+
+    #[test]
+    fn voiced_segments_locate_speech_in_the_extracted_audio() {
+        let one_sec = RATE as usize;
+        let mut samples = vec![0.0f32; one_sec];
+        samples.extend(tone(one_sec, 0.2));
+        samples.extend(vec![0.0f32; 3 * one_sec]);
+        samples.extend(tone(one_sec, 0.2));
+        samples.extend(vec![0.0f32; one_sec]);
+
+        let segments = run(&samples, 512);
+        let voiced = extract_voiced(&samples, &segments, RATE);
+        let located = voiced_segments(samples.len(), &segments, RATE);
+        assert_eq!(located.len(), 2);
+        for (segment, found) in segments.iter().zip(&located) {
+            assert_eq!(found.end - found.start, segment.end - segment.start);
+            assert_eq!(
+                &voiced[found.start..found.end],
+                &samples[segment.start..segment.end]
+            );
+        }
+    }
+
+    #[test]
+    fn segments_after_drops_and_clips_at_the_offset() {
+        let segments = [
+            SpeechSegment { start: 0, end: 100 },
+            SpeechSegment {
+                start: 150,
+                end: 300,
+            },
+            SpeechSegment {
+                start: 400,
+                end: 500,
+            },
+        ];
+        assert_eq!(
+            segments_after(&segments, 200),
+            vec![
+                SpeechSegment { start: 0, end: 100 },
+                SpeechSegment {
+                    start: 200,
+                    end: 300
+                },
+            ]
+        );
+        assert!(segments_after(&segments, 500).is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+//! Model integrity checks.
+//!
+//! A model's expected SHA-256 comes from its catalog entry when pinned there. Otherwise it
+//! is the hash recorded in `<file>.sha256` when the model was downloaded (checked against
+//! the hash Hugging Face reports for the file, when available), so corruption after the
+//! download is still caught.
+
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Lowercase hex SHA-256 of the file at `path`.
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("failed opening {}: {e}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file
+            .read(&mut buf)
+            .map_err(|e| format!("failed reading {}: {e}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// True for a 64-digit hex string, the form of a SHA-256.
+pub fn is_sha256_hex(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn recorded_hash_path(model_path: &Path) -> PathBuf {
+    let mut name = model_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".sha256");
+    model_path.with_file_name(name)
+}
+
+/// The hash recorded for `model_path` when it was downloaded, if any.
+pub fn recorded_hash(model_path: &Path) -> Option<String> {
+    let hash = fs::read_to_string(recorded_hash_path(model_path)).ok()?;
+    let hash = hash.trim().to_lowercase();
+    is_sha256_hex(&hash).then_some(hash)
+}
+
+pub fn record_hash(model_path: &Path, hash: &str) -> Result<(), String> {
+    let path = recorded_hash_path(model_path);
+    fs::write(&path, format!("{hash}\n"))
+        .map_err(|e| format!("failed writing {}: {e}", path.display()))
+}
+
+/// Delete a model and its recorded hash (e.g. before downloading it again).
+pub fn remove_model_file(model_path: &Path) -> Result<(), String> {
+    fs::remove_file(model_path)
+        .map_err(|e| format!("failed removing {}: {e}", model_path.display()))?;
+    let _ = fs::remove_file(recorded_hash_path(model_path));
+    Ok(())
+}
+
+/// Compare the model's hash with `expected`. `None` when there's nothing to compare with.
+pub fn verify_model_file(
+    model_path: &Path,
+    expected: Option<&str>,
+) -> Result<Option<bool>, String> {
+    let Some(expected) = expected else {
+        return Ok(None);
+    };
+    let actual = sha256_file(model_path)?;
+    Ok(Some(actual.eq_ignore_ascii_case(expected.trim())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn verifies_against_expected_and_recorded_hashes() {
+        let path = std::env::temp_dir().join(format!("dravis-verify-{}.bin", std::process::id()));
+        fs::write(&path, b"abc").expect("write model");
+
+        assert_eq!(sha256_file(&path).unwrap(), ABC_SHA256);
+        assert_eq!(verify_model_file(&path, None), Ok(None));
+        assert_eq!(
+            verify_model_file(&path, Some(&ABC_SHA256.to_uppercase())),
+            Ok(Some(true))
+        );
+        assert_eq!(
+            verify_model_file(&path, Some(&"0".repeat(64))),
+            Ok(Some(false))
+        );
+
+        assert_eq!(recorded_hash(&path), None);
+        record_hash(&path, ABC_SHA256).unwrap();
+        assert_eq!(recorded_hash(&path).as_deref(), Some(ABC_SHA256));
+
+        remove_model_file(&path).unwrap();
+        assert!(!path.exists());
+        assert_eq!(recorded_hash(&path), None);
+    }
+
+    #[test]
+    fn recognizes_sha256_hex() {
+        assert!(is_sha256_hex(ABC_SHA256));
+        assert!(!is_sha256_hex("abc123"));
+        assert!(!is_sha256_hex(&"g".repeat(64)));
+    }
+}
@@ -0,0 +1,154 @@
+//! Recording watchdog: maximum recording length and stuck hotkey state.
+//!
+//! A missed shortcut release leaves `InnerState.status` at `Recording` with the capture
+//! buffer growing without bound, and leaves `toggle_shortcut_held` set so every later
+//! press is ignored as key-repeat. A thread polls the state and
+//! - emits `recording_limit_warning` (remaining ms) shortly before `max_recording_secs`,
+//!   then stops the recording through `stop_recording_inner` at the limit;
+//! - resets `toggle_shortcut_held` / `press_instant` once they have outlived any recording.
+
+use crate::config::normalized_max_recording_secs;
+use crate::pipeline::stop_recording_inner;
+use crate::state::{AppState, AppStatus};
+use crate::{dlog, report_pipeline_error};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long before the cutoff the widget is warned.
+const LIMIT_WARNING: Duration = Duration::from_secs(10);
+
+/// Hotkey state is stale when the key has been "held" (or a press timestamp kept) this long
+/// without a recording running. Nobody holds the hotkey for 5 s without recording.
+const STALE_KEY_STATE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LimitAction {
+    Warn { remaining: Duration },
+    Stop,
+}
+
+/// Decide what the length limit requires for a recording `elapsed` long.
+fn limit_action(elapsed: Duration, max: Duration, warned: bool) -> Option<LimitAction> {
+    if elapsed >= max {
+        return Some(LimitAction::Stop);
+    }
+    let remaining = max - elapsed;
+    if !warned && remaining <= LIMIT_WARNING {
+        return Some(LimitAction::Warn { remaining });
+    }
+    None
+}
+
+/// Per-recording bookkeeping so each warning and cutoff fires once.
+#[derive(Default)]
+struct WatchState {
+    warned: bool,
+    stopped: bool,
+    /// First poll that saw hotkey state without a recording.
+    key_state_since: Option<Instant>,
+}
+
+pub fn spawn_recording_watchdog(app: AppHandle) {
+    let spawned = std::thread::Builder::new()
+        .name("recording-watchdog".to_string())
+        .spawn(move || {
+            let mut watch = WatchState::default();
+            loop {
+                std::thread::sleep(POLL_INTERVAL);
+                check_recording(&app, &mut watch);
+            }
+        });
+
+    if let Err(e) = spawned {
+        dlog!("watchdog: failed to start recording watchdog: {e}");
+    }
+}
+
+fn check_recording(app: &AppHandle, watch: &mut WatchState) {
+    let state = app.state::<AppState>();
+    let Ok(mut inner) = state.inner_state.lock() else {
+        return;
+    };
+
+    let recording = inner.status == AppStatus::Recording;
+    let has_key_state = inner.toggle_shortcut_held || inner.press_instant.is_some();
+    if recording || !has_key_state {
+        watch.key_state_since = None;
+    } else {
+        let since = *watch.key_state_since.get_or_insert_with(Instant::now);
+        if since.elapsed() >= STALE_KEY_STATE {
+            dlog!(
+                "watchdog: resetting stale hotkey state (held: {}, press_instant: {:?})",
+                inner.toggle_shortcut_held,
+                inner.press_instant.map(|t| t.elapsed())
+            );
+            inner.toggle_shortcut_held = false;
+            inner.press_instant = None;
+            watch.key_state_since = None;
+        }
+    }
+
+    let elapsed = match inner.recorder.recording_elapsed() {
+        Some(elapsed) if recording => elapsed,
+        _ => {
+            watch.warned = false;
+            watch.stopped = false;
+            return;
+        }
+    };
+    if watch.stopped {
+        return;
+    }
+
+    let max_secs = normalized_max_recording_secs(inner.config.general.max_recording_secs);
+    let action = limit_action(elapsed, Duration::from_secs(max_secs as u64), watch.warned);
+    drop(inner);
+
+    match action {
+        Some(LimitAction::Warn { remaining }) => {
+            watch.warned = true;
+            let _ = app.emit("recording_limit_warning", remaining.as_millis() as u64);
+        }
+        Some(LimitAction::Stop) => {
+            watch.stopped = true;
+            dlog!("watchdog: recording reached the {max_secs} s limit, stopping");
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(err) = stop_recording_inner(app.clone()).await {
+                    report_pipeline_error(&app, err);
+                }
+            });
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn warns_once_before_the_limit() {
+        assert_eq!(limit_action(Duration::from_secs(30), MAX, false), None);
+        assert_eq!(
+            limit_action(Duration::from_secs(52), MAX, false),
+            Some(LimitAction::Warn {
+                remaining: Duration::from_secs(8)
+            })
+        );
+        assert_eq!(limit_action(Duration::from_secs(55), MAX, true), None);
+    }
+
+    #[test]
+    fn stops_at_the_limit_even_without_a_warning() {
+        assert_eq!(
+            limit_action(Duration::from_secs(61), MAX, false),
+            Some(LimitAction::Stop)
+        );
+        assert_eq!(limit_action(MAX, MAX, true), Some(LimitAction::Stop));
+    }
+}
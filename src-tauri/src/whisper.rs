@@ -4,17 +4,57 @@
 //! The `WhisperContext` is pre-loaded on startup and cached in `AppState`.
 //! Dictionary words are fed as a glossary in `initial_prompt` — this is style
 //! conditioning (not instruction following), limited to ~224 tokens (~850 chars).
+//!
+//! Long recordings are transcribed in chunks (see `chunking`); each chunk's text replaces
+//! the style sentence in the next chunk's prompt so context carries across the cut. The
+//! first chunk is conditioned the same way on `TranscribeOptions::context`, e.g. the end of
+//! the previous dictation (see `carryover`). The glossary keeps priority in these prompts.
+//!
+//! With `language = "auto"` (multilingual models only) the language is detected once on
+//! the first 30 s and every chunk is decoded in it. With `translate` set, Whisper's
+//! translate task turns speech in any language into English text.
+//!
+//! Sampling, temperature fallback and thresholds come from `[decoding]` in the config
+//! (see `config::DecodingSettings`).
+//!
+//! Results keep Whisper's segments with their times and per-token probabilities, so later
+//! stages can look at timing and confidence instead of only the flattened text. Times are
+//! on the VAD-trimmed audio, where pauses are already shortened. Segments Whisper made up
+//! (subtitle phrases, loops, text over silence) are removed first, see `hallucination`.
 
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::chunking::{plan_chunks, stitch};
 use crate::config::model_file_path;
-use crate::config::AppConfig;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
-
-/// Sweet spot for M-series chips; higher values cause thread contention without measurable gain.
-const WHISPER_N_THREADS: i32 = 4;
+use crate::config::{AppConfig, DecodingSettings, HallucinationConfig, StyleConfig, LANGUAGE_AUTO};
+use crate::dlog;
+use crate::hallucination;
+use crate::vad::SpeechSegment;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use whisper_rs::{
+    FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
+    WhisperToken,
+};
 
 /// Whisper's hard limit is ~890 characters (~224 tokens). 850 leaves margin to avoid mid-word truncation.
 const WHISPER_MAX_PROMPT_CHARS: usize = 850;
 
+/// Previous-chunk text carried into the next chunk's prompt. Leaves most of the prompt
+/// budget for the glossary.
+const CHUNK_CONTEXT_CHARS: usize = 300;
+
+/// Previous text kept even when a long glossary would want the whole prompt.
+const CHUNK_CONTEXT_MIN_CHARS: usize = 100;
+
+/// Audio used for language detection — Whisper looks at one 30 s window.
+const LANGUAGE_DETECT_SAMPLES: usize = 30 * TARGET_SAMPLE_RATE as usize;
+
+/// Longest style sentence; a longer configured one is cut so the glossary keeps room.
+const STYLE_MAX_CHARS: usize = 400;
+
+const DEFAULT_STYLE_PROMPT: &str = "I discussed the project requirements with the team, then reviewed the implementation details and pushed the changes.";
+
 pub struct WhisperEngine {
     model_path: std::path::PathBuf,
 }
@@ -35,12 +75,205 @@ impl WhisperEngine {
     }
 }
 
+/// Language Whisper detected for `language = "auto"`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectedLanguage {
+    /// Whisper language code, e.g. `"pt"`.
+    pub code: String,
+    pub probability: f32,
+}
+
+/// One decoded token. Times are in ms from the start of the transcribed audio.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Token {
+    pub text: String,
+    /// Whisper's probability for the token, 0.0–1.0.
+    pub probability: f32,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+/// One Whisper segment (roughly a phrase or sentence).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Segment {
+    pub text: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// Text tokens only; timestamps and other special tokens are left out.
+    pub tokens: Vec<Token>,
+    /// Mean token probability; 0 for a segment without tokens.
+    pub probability: f32,
+}
+
+/// Result of transcribing a buffer.
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcript {
+    pub text: String,
+    pub segments: Vec<Segment>,
+    /// Set when the language was detected rather than configured.
+    pub detected_language: Option<DetectedLanguage>,
+}
+
+impl Transcript {
+    /// Mean probability of all tokens; `None` without tokens.
+    pub fn mean_probability(&self) -> Option<f32> {
+        let (sum, count) = self
+            .segments
+            .iter()
+            .flat_map(|segment| &segment.tokens)
+            .fold((0.0f32, 0usize), |(sum, count), token| {
+                (sum + token.probability, count + 1)
+            });
+        (count > 0).then(|| sum / count as f32)
+    }
+
+    /// Move every time `samples` (at 16 kHz) later, e.g. when the audio started after a
+    /// live-transcribed prefix.
+    pub fn offset(&mut self, samples: usize) {
+        let ms = samples_to_ms(samples);
+        for segment in &mut self.segments {
+            segment.start_ms += ms;
+            segment.end_ms += ms;
+            for token in &mut segment.tokens {
+                token.start_ms += ms;
+                token.end_ms += ms;
+            }
+        }
+    }
+}
+
+/// Everything besides the audio that shapes one transcription.
+#[derive(Debug, Clone, Default)]
+pub struct TranscribeOptions {
+    /// Whisper language code or [`LANGUAGE_AUTO`].
+    pub language: String,
+    pub dictionary_words: Vec<String>,
+    pub style: StyleConfig,
+    /// Text that came before the audio (committed by live transcription, or the previous
+    /// dictation carried over); when non-empty it conditions the first chunk like a
+    /// previous chunk.
+    pub context: String,
+    /// Output English whatever the spoken language (multilingual models only).
+    pub translate: bool,
+    pub decoding: DecodingSettings,
+    /// CPU threads to decode with (see `config::whisper_threads`).
+    pub threads: usize,
+    pub hallucinations: HallucinationConfig,
+    /// Decode every chunk with an empty initial prompt (no style, glossary or context),
+    /// so runs over the same audio do the same work. Used by `benchmark`.
+    pub no_prompt: bool,
+}
+
+/// True for a language code Whisper knows (`"en"`, `"pt"`, ...). `"auto"` is not a code.
+pub fn is_known_language(code: &str) -> bool {
+    code != LANGUAGE_AUTO && whisper_rs::get_lang_id(code).is_some()
+}
+
 pub fn load_context(model_path: &str) -> Result<WhisperContext, String> {
     let ctx_params = WhisperContextParameters::default();
     WhisperContext::new_with_params(model_path, ctx_params)
         .map_err(|e| format!("failed to load whisper model: {e}"))
 }
 
+/// Built-in style sentence for a Whisper language code; English for languages without one.
+pub fn default_style_prompt(language: &str) -> &'static str {
+    match language {
+        "pt" => "Discuti os requisitos do projeto com a equipe, depois revisei os detalhes da implementação e enviei as alterações.",
+        "es" => "Hablé de los requisitos del proyecto con el equipo, luego revisé los detalles de la implementación y subí los cambios.",
+        "fr" => "J'ai discuté des exigences du projet avec l'équipe, puis j'ai relu les détails de l'implémentation et envoyé les modifications.",
+        "de" => "Ich habe die Projektanforderungen mit dem Team besprochen, dann die Details der Implementierung geprüft und die Änderungen hochgeladen.",
+        "it" => "Ho discusso i requisiti del progetto con il team, poi ho rivisto i dettagli dell'implementazione e ho caricato le modifiche.",
+        "nl" => "Ik heb de projecteisen met het team besproken, daarna de details van de implementatie bekeken en de wijzigingen gepusht.",
+        "ja" => "チームとプロジェクトの要件について話し合い、実装の詳細を確認してから変更をプッシュしました。",
+        "zh" => "我和团队讨论了项目需求，然后检查了实现细节，并提交了修改。",
+        _ => DEFAULT_STYLE_PROMPT,
+    }
+}
+
+/// Style sentence for output in `language`: the configured one (see `config::StyleConfig`)
+/// or the built-in one, cut to [`STYLE_MAX_CHARS`].
+fn style_sentence<'a>(options: &'a TranscribeOptions, language: &str) -> &'a str {
+    // Translations come out in English whatever was spoken.
+    let language = if options.translate { "en" } else { language };
+    let style = options
+        .style
+        .style_prompt(language)
+        .unwrap_or_else(|| default_style_prompt(language));
+    head_at_word_boundary(style, STYLE_MAX_CHARS)
+}
+
+/// The prompt a transcription with `options` starts with (no previous text), for
+/// `preview_initial_prompt`. With `language = "auto"` the English style is shown.
+pub fn initial_prompt(options: &TranscribeOptions) -> InitialPrompt {
+    build_prompt(
+        style_sentence(options, &options.language),
+        &options.dictionary_words,
+    )
+}
+
+/// Prompt for a chunk after the first: the tail of the text so far sets the style instead
+/// of the style sentence. The tail gets the room the glossary leaves, between
+/// [`CHUNK_CONTEXT_MIN_CHARS`] and [`CHUNK_CONTEXT_CHARS`].
+fn build_chunk_prompt(previous_text: &str, style: &str, dictionary_words: &[String]) -> String {
+    let glossary_chars = match dictionary_words.len() {
+        0 => 0,
+        n => {
+            " Glossary: ".len()
+                + dictionary_words.iter().map(String::len).sum::<usize>()
+                + ", ".len() * (n - 1)
+        }
+    };
+    let room = WHISPER_MAX_PROMPT_CHARS
+        .saturating_sub(glossary_chars)
+        .clamp(CHUNK_CONTEXT_MIN_CHARS, CHUNK_CONTEXT_CHARS);
+    let tail = tail_at_word_boundary(previous_text, room);
+    if tail.is_empty() {
+        return build_prompt(style, dictionary_words).prompt;
+    }
+    build_prompt(tail, dictionary_words).prompt
+}
+
+/// First `max` bytes of `text`, ending at a word boundary.
+fn head_at_word_boundary(text: &str, max: usize) -> &str {
+    let text = text.trim();
+    if text.len() <= max {
+        return text;
+    }
+    let mut cut = max;
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    match text[..cut].rfind(char::is_whitespace) {
+        Some(space) => text[..space].trim_end(),
+        None => &text[..cut],
+    }
+}
+
+/// Last `max` bytes of `text`, starting at a word boundary.
+fn tail_at_word_boundary(text: &str, max: usize) -> &str {
+    let text = text.trim();
+    if text.len() <= max {
+        return text;
+    }
+    let mut cut = text.len() - max;
+    while !text.is_char_boundary(cut) {
+        cut += 1;
+    }
+    match text[cut..].find(char::is_whitespace) {
+        Some(space) => text[cut + space..].trim_start(),
+        None => &text[cut..],
+    }
+}
+
+/// An initial prompt and which glossary terms made it in.
+#[derive(Debug, Clone, Serialize)]
+pub struct InitialPrompt {
+    pub prompt: String,
+    pub included: Vec<String>,
+    /// Terms left out because the prompt was full.
+    pub dropped: Vec<String>,
+}
+
 /// Build the initial_prompt for Whisper conditioning.
 ///
 /// Structure (within 224 token / ~890 char limit):
@@ -49,74 +282,439 @@ pub fn load_context(model_path: &str) -> Result<WhisperContext, String> {
 ///
 /// Whisper treats this as "previous transcript context", NOT as instructions.
 /// It follows the *style* of the prompt and recognizes glossary terms more accurately.
-fn build_initial_prompt(dictionary_words: &[String]) -> String {
-    let style = "I discussed the project requirements with the team, then reviewed the implementation details and pushed the changes.";
-
-    if dictionary_words.is_empty() {
-        return style.to_string();
-    }
-
-    let glossary = dictionary_words.join(", ");
-    let prompt = format!("{style} Glossary: {glossary}");
-
-    // Whisper hard limit: 224 tokens (~890 chars). Truncate glossary if needed.
-    // Keep a safe margin — cut at WHISPER_MAX_PROMPT_CHARS to avoid mid-word truncation.
-    if prompt.len() > WHISPER_MAX_PROMPT_CHARS {
-        let available = WHISPER_MAX_PROMPT_CHARS - style.len() - " Glossary: ".len();
-        let mut truncated = String::new();
-        for word in dictionary_words {
-            let next = if truncated.is_empty() {
-                word.clone()
-            } else {
-                format!(", {word}")
-            };
-            if truncated.len() + next.len() > available {
-                break;
-            }
-            truncated.push_str(&next);
+/// `dictionary_words` should be ranked (see `glossary`): terms that don't fit are dropped
+/// from the end.
+fn build_prompt(style: &str, dictionary_words: &[String]) -> InitialPrompt {
+    // Whisper hard limit: 224 tokens (~890 chars). Keep a safe margin — stop adding terms
+    // at WHISPER_MAX_PROMPT_CHARS so none is cut mid-word. A term that doesn't fit is
+    // skipped; a shorter one after it may still fit.
+    let available = WHISPER_MAX_PROMPT_CHARS.saturating_sub(style.len() + " Glossary: ".len());
+    let mut glossary = String::new();
+    let mut included = Vec::new();
+    let mut dropped = Vec::new();
+    for word in dictionary_words {
+        let separator = if glossary.is_empty() { "" } else { ", " };
+        if glossary.len() + separator.len() + word.len() > available {
+            dropped.push(word.clone());
+            continue;
         }
-        format!("{style} Glossary: {truncated}")
+        glossary.push_str(separator);
+        glossary.push_str(word);
+        included.push(word.clone());
+    }
+
+    let prompt = if included.is_empty() {
+        style.to_string()
     } else {
-        prompt
+        format!("{style} Glossary: {glossary}")
+    };
+    InitialPrompt {
+        prompt,
+        included,
+        dropped,
     }
 }
 
+/// Transcribe 16 kHz mono audio. Long audio is split into chunks (see `chunking`), and
+/// `on_progress(done, total)` is called as each chunk finishes. `speech` is where the VAD
+/// heard speech in `audio`, for the hallucination filter.
 pub fn transcribe_with_ctx(
     ctx: &WhisperContext,
     audio: &[f32],
+    speech: &[SpeechSegment],
+    options: &TranscribeOptions,
+    on_progress: &dyn Fn(usize, usize),
+) -> Result<Transcript, String> {
+    let mut state = ctx
+        .create_state()
+        .map_err(|e| format!("failed creating whisper state: {e}"))?;
+    let (language, detected_language) =
+        resolve_language(&mut state, audio, &options.language, options.threads.max(1))?;
+
+    let chunks = plan_chunks(audio, TARGET_SAMPLE_RATE);
+    if chunks.len() > 1 {
+        dlog!(
+            "whisper: transcribing {} samples in {} chunks",
+            audio.len(),
+            chunks.len()
+        );
+    }
+
+    let style = style_sentence(options, &language);
+    let eot = ctx.token_eot();
+    let mut text = String::new();
+    let mut segments = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let previous = if i == 0 { &options.context } else { &text };
+        let initial_prompt = if options.no_prompt {
+            String::new()
+        } else {
+            build_chunk_prompt(previous, style, &options.dictionary_words)
+        };
+        let params = decode_params(&language, &initial_prompt, options);
+        let chunk_audio = &audio[chunk.start..chunk.end];
+        let offset_ms = samples_to_ms(chunk.start);
+        let chunk_segments = transcribe_chunk(&mut state, chunk_audio, params, eot, offset_ms)?;
+        let chunk_segments =
+            hallucination::filter_segments(chunk_segments, speech, &options.hallucinations);
+        let chunk_text = join_segments(&chunk_segments);
+
+        // Only chunks cut without a pause overlap the previous one.
+        let overlapped = i > 0 && chunk.start < chunks[i - 1].end;
+        let overlap_end_ms = overlapped.then(|| samples_to_ms(chunks[i - 1].end));
+        append_segments(&mut segments, chunk_segments, overlap_end_ms);
+        text = if overlapped {
+            stitch(&text, &chunk_text)
+        } else if text.is_empty() {
+            chunk_text
+        } else if chunk_text.is_empty() {
+            text
+        } else {
+            format!("{text} {chunk_text}")
+        };
+        on_progress(i + 1, chunks.len());
+    }
+
+    Ok(Transcript {
+        text,
+        segments,
+        detected_language,
+    })
+}
+
+fn samples_to_ms(samples: usize) -> u64 {
+    (samples as u64 * 1000) / TARGET_SAMPLE_RATE as u64
+}
+
+fn join_segments(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Add a chunk's segments. After an overlapping cut, segments that end inside the overlap
+/// were already transcribed from the previous chunk and are skipped; the text itself is
+/// de-duplicated by `stitch`.
+fn append_segments(segments: &mut Vec<Segment>, next: Vec<Segment>, overlap_end_ms: Option<u64>) {
+    let overlap_end_ms = overlap_end_ms.unwrap_or(0);
+    segments.extend(
+        next.into_iter()
+            .filter(|segment| segment.end_ms > overlap_end_ms),
+    );
+}
+
+/// Resolve [`LANGUAGE_AUTO`] with Whisper's language detection on the start of `audio`.
+/// Any other language is returned as is.
+fn resolve_language(
+    state: &mut WhisperState,
+    audio: &[f32],
     language: &str,
-    dictionary_words: &[String],
-) -> Result<String, String> {
+    threads: usize,
+) -> Result<(String, Option<DetectedLanguage>), String> {
+    if language != LANGUAGE_AUTO {
+        return Ok((language.to_string(), None));
+    }
+
+    let window = &audio[..audio.len().min(LANGUAGE_DETECT_SAMPLES)];
+    state
+        .pcm_to_mel(window, threads)
+        .map_err(|e| format!("failed computing mel spectrogram: {e}"))?;
+    let (id, probabilities) = state
+        .lang_detect(0, threads)
+        .map_err(|e| format!("language detection failed: {e}"))?;
+    let code = whisper_rs::get_lang_str(id)
+        .ok_or_else(|| format!("language detection returned unknown id {id}"))?;
+    let probability = probabilities.get(id as usize).copied().unwrap_or(0.0);
+    dlog!("whisper: detected language {code} (p={probability:.2})");
+
+    Ok((
+        code.to_string(),
+        Some(DetectedLanguage {
+            code: code.to_string(),
+            probability,
+        }),
+    ))
+}
+
+/// A decoded segment and where it ends, in samples from the start of the audio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedSegment {
+    pub text: String,
+    pub end: usize,
+}
+
+/// Transcribe a short window (under 30 s, no chunking) and keep segment boundaries.
+/// Used by live transcription with the recorder's `speech` in the window; setting `abort`
+/// makes an in-flight pass return an error.
+pub fn transcribe_segments(
+    ctx: &WhisperContext,
+    audio: &[f32],
+    speech: &[SpeechSegment],
+    options: &TranscribeOptions,
+    abort: Arc<AtomicBool>,
+) -> Result<Vec<TimedSegment>, String> {
     let mut state = ctx
         .create_state()
         .map_err(|e| format!("failed creating whisper state: {e}"))?;
+    let style = style_sentence(options, &options.language);
+    let initial_prompt = build_chunk_prompt(&options.context, style, &options.dictionary_words);
+    let mut params = decode_params(&options.language, &initial_prompt, options);
+    params.set_abort_callback_safe(move || abort.load(Ordering::Relaxed));
+    let segments = transcribe_chunk(&mut state, audio, params, ctx.token_eot(), 0)?;
+    let segments = hallucination::filter_segments(segments, speech, &options.hallucinations);
 
-    let initial_prompt = build_initial_prompt(dictionary_words);
+    Ok(segments
+        .into_iter()
+        .map(|segment| TimedSegment {
+            end: (segment.end_ms as usize * TARGET_SAMPLE_RATE as usize / 1000).min(audio.len()),
+            text: segment.text,
+        })
+        .collect())
+}
 
-    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-    params.set_translate(false);
+fn decode_params<'a>(
+    language: &'a str,
+    initial_prompt: &str,
+    options: &TranscribeOptions,
+) -> FullParams<'a, 'a> {
+    let decoding = &options.decoding;
+    let mut params = FullParams::new(sampling_strategy(decoding));
+    params.set_translate(options.translate);
     params.set_language(Some(language));
-    params.set_n_threads(WHISPER_N_THREADS);
-    params.set_initial_prompt(&initial_prompt);
-    params.set_suppress_blank(true);
-    params.set_suppress_non_speech_tokens(true);
+    params.set_n_threads(options.threads.max(1) as i32);
+    params.set_initial_prompt(initial_prompt);
+    params.set_temperature(decoding.temperature);
+    params.set_temperature_inc(decoding.temperature_inc);
+    params.set_entropy_thold(decoding.entropy_thold);
+    params.set_logprob_thold(decoding.logprob_thold);
+    params.set_no_speech_thold(decoding.no_speech_thold);
+    params.set_suppress_blank(decoding.suppress_blank);
+    params.set_suppress_non_speech_tokens(decoding.suppress_non_speech_tokens);
+    params.set_token_timestamps(true);
+    params
+}
 
+fn sampling_strategy(decoding: &DecodingSettings) -> SamplingStrategy {
+    if decoding.beam_size > 1 {
+        SamplingStrategy::BeamSearch {
+            beam_size: decoding.beam_size as i32,
+            // Ignored by whisper.cpp; -1 is its default.
+            patience: -1.0,
+        }
+    } else {
+        SamplingStrategy::Greedy { best_of: 1 }
+    }
+}
+
+/// Decode `audio` and read back its segments, with times shifted by `offset_ms`.
+/// Tokens from `eot` up are Whisper's special tokens (timestamps, language tags, ...).
+fn transcribe_chunk(
+    state: &mut WhisperState,
+    audio: &[f32],
+    params: FullParams,
+    eot: WhisperToken,
+    offset_ms: u64,
+) -> Result<Vec<Segment>, String> {
     state
         .full(params, audio)
         .map_err(|e| format!("whisper inference failed: {e}"))?;
 
-    let mut out = String::new();
-    let segments = state
+    let n_segments = state
         .full_n_segments()
         .map_err(|e| format!("failed reading whisper segments: {e}"))?;
-
-    for i in 0..segments {
-        let segment = state
+    let mut segments = Vec::new();
+    for i in 0..n_segments {
+        let text = state
             .full_get_segment_text(i)
             .map_err(|e| format!("failed reading segment text: {e}"))?;
-        out.push_str(segment.trim());
-        out.push(' ');
+        let t0 = state
+            .full_get_segment_t0(i)
+            .map_err(|e| format!("failed reading segment time: {e}"))?;
+        let t1 = state
+            .full_get_segment_t1(i)
+            .map_err(|e| format!("failed reading segment time: {e}"))?;
+
+        let n_tokens = state
+            .full_n_tokens(i)
+            .map_err(|e| format!("failed reading segment tokens: {e}"))?;
+        let mut tokens = Vec::new();
+        for j in 0..n_tokens {
+            let data = state
+                .full_get_token_data(i, j)
+                .map_err(|e| format!("failed reading token data: {e}"))?;
+            if data.id >= eot {
+                continue;
+            }
+            let text = state
+                .full_get_token_text_lossy(i, j)
+                .map_err(|e| format!("failed reading token text: {e}"))?;
+            tokens.push(Token {
+                text,
+                probability: data.p,
+                start_ms: whisper_time_to_ms(data.t0) + offset_ms,
+                end_ms: whisper_time_to_ms(data.t1) + offset_ms,
+            });
+        }
+
+        segments.push(Segment {
+            text: text.trim().to_string(),
+            start_ms: whisper_time_to_ms(t0) + offset_ms,
+            end_ms: whisper_time_to_ms(t1) + offset_ms,
+            probability: mean_probability(&tokens),
+            tokens,
+        });
+    }
+    Ok(segments)
+}
+
+/// Whisper times are in 10 ms units.
+fn whisper_time_to_ms(t: i64) -> u64 {
+    t.max(0) as u64 * 10
+}
+
+fn mean_probability(tokens: &[Token]) -> f32 {
+    if tokens.is_empty() {
+        return 0.0;
+    }
+    tokens.iter().map(|token| token.probability).sum::<f32>() / tokens.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_prompt_carries_the_tail_of_previous_text() {
+        let previous = "word ".repeat(200) + "and the final sentence.";
+        let prompt = build_chunk_prompt(&previous, DEFAULT_STYLE_PROMPT, &["Tauri".to_string()]);
+        assert!(prompt.len() <= WHISPER_MAX_PROMPT_CHARS);
+        assert!(prompt.contains("and the final sentence. Glossary: Tauri"));
+        assert!(!prompt.starts_with(' '));
+        assert!(!prompt.contains(DEFAULT_STYLE_PROMPT));
+    }
+
+    #[test]
+    fn initial_prompt_reports_terms_that_did_not_fit() {
+        let mut words: Vec<String> = (0..200).map(|i| format!("term{i:03}")).collect();
+        words.push("Go".to_string());
+        let initial = build_prompt(DEFAULT_STYLE_PROMPT, &words);
+        assert!(initial.prompt.len() <= WHISPER_MAX_PROMPT_CHARS);
+        assert_eq!(initial.included[0], "term000");
+        assert_eq!(initial.included.len() + initial.dropped.len(), words.len());
+        assert!(initial.dropped.contains(&"term199".to_string()));
+        // Short terms still fill the space the long ones left.
+        assert_eq!(initial.included.last().map(String::as_str), Some("Go"));
+    }
+
+    #[test]
+    fn chunk_prompt_shortens_previous_text_to_keep_the_glossary() {
+        let previous = "word ".repeat(200) + "and the final sentence.";
+        let words: Vec<String> = (0..60).map(|i| format!("term{i:03}")).collect();
+        let prompt = build_chunk_prompt(&previous, DEFAULT_STYLE_PROMPT, &words);
+        assert!(prompt.len() <= WHISPER_MAX_PROMPT_CHARS);
+        assert!(prompt.ends_with("term059"));
+        assert!(prompt.contains("and the final sentence. Glossary: term000"));
+
+        // A glossary that can't fit anyway still leaves some previous text.
+        let words: Vec<String> = (0..200).map(|i| format!("term{i:03}")).collect();
+        let prompt = build_chunk_prompt(&previous, DEFAULT_STYLE_PROMPT, &words);
+        let tail = prompt.split(" Glossary: ").next().unwrap_or_default();
+        assert!(tail.len() > CHUNK_CONTEXT_MIN_CHARS - "word ".len());
+    }
+
+    #[test]
+    fn chunk_prompt_falls_back_to_style_sentence() {
+        assert_eq!(
+            build_chunk_prompt("  ", DEFAULT_STYLE_PROMPT, &[]),
+            DEFAULT_STYLE_PROMPT
+        );
     }
 
-    Ok(out.trim().to_string())
+    #[test]
+    fn style_follows_language_and_long_styles_leave_room_for_the_glossary() {
+        let mut options = TranscribeOptions {
+            language: "pt".to_string(),
+            dictionary_words: vec!["Tauri".to_string()],
+            ..TranscribeOptions::default()
+        };
+        assert_eq!(style_sentence(&options, "pt"), default_style_prompt("pt"));
+        assert_eq!(style_sentence(&options, "sv"), DEFAULT_STYLE_PROMPT);
+        options.translate = true;
+        assert_eq!(style_sentence(&options, "pt"), DEFAULT_STYLE_PROMPT);
+
+        options.translate = false;
+        options.style.prompt = "palavra ".repeat(200);
+        let style = style_sentence(&options, "pt");
+        assert!(style.len() <= STYLE_MAX_CHARS);
+        assert!(style.ends_with("palavra"));
+        let initial = initial_prompt(&options);
+        assert_eq!(initial.included, ["Tauri"]);
+        assert!(initial.prompt.len() <= WHISPER_MAX_PROMPT_CHARS);
+    }
+
+    fn segment(text: &str, start_ms: u64, end_ms: u64, probabilities: &[f32]) -> Segment {
+        let tokens: Vec<Token> = probabilities
+            .iter()
+            .map(|&probability| Token {
+                text: text.to_string(),
+                probability,
+                start_ms,
+                end_ms,
+            })
+            .collect();
+        Segment {
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            probability: mean_probability(&tokens),
+            tokens,
+        }
+    }
+
+    #[test]
+    fn overlapping_chunks_skip_segments_already_transcribed() {
+        let mut segments = vec![segment("first part", 0, 27_500, &[0.9])];
+        let next = vec![
+            segment("part", 26_000, 27_800, &[0.8]),
+            segment("second part", 27_800, 40_000, &[0.5, 0.7]),
+        ];
+        append_segments(&mut segments, next, Some(28_000));
+        let texts: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["first part", "second part"]);
+        assert!((segments[1].probability - 0.6).abs() < 1e-6);
+        assert_eq!(join_segments(&segments), "first part second part");
+    }
+
+    #[test]
+    fn offset_moves_segment_and_token_times() {
+        let mut transcript = Transcript {
+            text: "hello".to_string(),
+            segments: vec![segment("hello", 100, 600, &[1.0])],
+            detected_language: None,
+        };
+        transcript.offset(TARGET_SAMPLE_RATE as usize * 2);
+        let segment = &transcript.segments[0];
+        assert_eq!((segment.start_ms, segment.end_ms), (2_100, 2_600));
+        assert_eq!(segment.tokens[0].start_ms, 2_100);
+        assert_eq!(mean_probability(&[]), 0.0);
+    }
+
+    #[test]
+    fn transcript_probability_weighs_every_token() {
+        let mut transcript = Transcript {
+            text: "one two three".to_string(),
+            segments: vec![
+                segment("one", 0, 500, &[0.9]),
+                segment("two three", 500, 1500, &[0.5, 0.7]),
+            ],
+            detected_language: None,
+        };
+        let mean = transcript.mean_probability().expect("has tokens");
+        assert!((mean - 0.7).abs() < 1e-6);
+        transcript.segments.clear();
+        assert_eq!(transcript.mean_probability(), None);
+    }
 }
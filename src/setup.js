@@ -2,13 +2,20 @@ const MODEL_SIZES = {
   "base.en": "142 MB",
   "small.en": "466 MB",
   "large-v3-turbo": "809 MB",
+  base: "142 MB",
+  small: "466 MB",
+  medium: "1.5 GB",
 };
 
 const MODEL_LABELS = {
   "base.en": "Base",
   "small.en": "Small",
   "large-v3-turbo": "Large Turbo",
+  base: "Base Multilingual",
+  small: "Small Multilingual",
+  medium: "Medium Multilingual",
 };
+const LANGUAGE_AUTO = "auto";
 const PROMPT_PROVIDER_ANTHROPIC = "anthropic";
 const PROMPT_PROVIDER_OPENAI = "openai";
 const PROMPT_PROVIDER_OPENROUTER = "openrouter";
@@ -25,6 +32,7 @@ const PROVIDER_KEY_FIELDS = {
 
 let currentModel = "base.en";
 let vocabWords = [];
+let pinnedWords = [];
 let vocabReplacements = [];
 let promptModeConfig = {
   enabled: false,
@@ -32,6 +40,12 @@ let promptModeConfig = {
   model: PROMPT_MODEL_DEFAULTS[PROMPT_PROVIDER_ANTHROPIC],
   api_key: "",
 };
+let warmMicEnabled = false;
+let liveTranscriptionEnabled = false;
+let carryContextEnabled = false;
+let translateEnabled = false;
+let decodingPreset = "balanced";
+let currentLanguage = "en";
 let dictErrorTimer = null;
 
 function showDictError(dictErrorMsgEl, msg) {
@@ -50,7 +64,16 @@ function renderVocabList(vocabListEl) {
   vocabListEl.innerHTML = "";
   vocabWords.forEach((word, index) => {
     const chip = document.createElement("span");
-    chip.className = "tag-chip";
+    const pinned = pinnedWords.includes(word);
+    chip.className = pinned ? "tag-chip pinned" : "tag-chip";
+    const pinBtn = document.createElement("button");
+    pinBtn.className = "tag-chip-remove tag-chip-pin";
+    pinBtn.type = "button";
+    pinBtn.setAttribute("aria-label", pinned ? `Unpin ${word}` : `Pin ${word}`);
+    pinBtn.setAttribute("aria-pressed", String(pinned));
+    pinBtn.textContent = pinned ? "★" : "☆";
+    pinBtn.addEventListener("click", () => togglePinnedWord(invoke, vocabListEl, dictErrorMsgEl, word));
+    chip.appendChild(pinBtn);
     chip.appendChild(document.createTextNode(word));
     const removeBtn = document.createElement("button");
     removeBtn.className = "tag-chip-remove";
@@ -101,6 +124,7 @@ let dictErrorMsgEl;
 
 async function removeVocabWord(invokeFn, vocabListEl, errorEl, index) {
   const previous = vocabWords;
+  const removed = vocabWords[index];
   vocabWords = vocabWords.filter((_, i) => i !== index);
   renderVocabList(vocabListEl);
   try {
@@ -109,6 +133,38 @@ async function removeVocabWord(invokeFn, vocabListEl, errorEl, index) {
     vocabWords = previous;
     renderVocabList(vocabListEl);
     showDictError(errorEl, `Could not save vocabulary: ${error}`);
+    return;
+  }
+  if (pinnedWords.includes(removed)) {
+    await togglePinnedWord(invokeFn, vocabListEl, errorEl, removed);
+  }
+}
+
+async function togglePinnedWord(invokeFn, vocabListEl, errorEl, word) {
+  const previous = pinnedWords;
+  pinnedWords = pinnedWords.includes(word) ? pinnedWords.filter((w) => w !== word) : [...pinnedWords, word];
+  renderVocabList(vocabListEl);
+  try {
+    await invokeFn("set_pinned_words", { words: pinnedWords });
+  } catch (error) {
+    pinnedWords = previous;
+    renderVocabList(vocabListEl);
+    showDictError(errorEl, `Could not save pinned words: ${error}`);
+  }
+}
+
+async function previewInitialPrompt(invokeFn, previewEl) {
+  previewEl.classList.remove("hidden");
+  try {
+    const preview = await invokeFn("preview_initial_prompt");
+    const included = preview.included || [];
+    const dropped = preview.dropped || [];
+    previewEl.textContent = dropped.length
+      ? `${included.length} terms fit in the prompt. Left out: ${dropped.join(", ")}.`
+      : `All ${included.length} terms fit in the prompt.`;
+    previewEl.title = preview.prompt || "";
+  } catch (error) {
+    previewEl.textContent = `Could not build prompt: ${error}`;
   }
 }
 
@@ -259,6 +315,146 @@ async function updatePromptMode(
   }
 }
 
+async function saveWarmMic(invokeFn, warmMicToggleEl) {
+  const previous = warmMicEnabled;
+  warmMicEnabled = warmMicToggleEl.checked;
+  try {
+    const config = await invokeFn("set_warm_mic", { enabled: warmMicEnabled });
+    warmMicEnabled = Boolean(config?.audio?.warm_mic);
+  } catch (error) {
+    warmMicEnabled = previous;
+    showDictError(dictErrorMsgEl, `Could not change warm mic: ${error}`);
+  }
+  warmMicToggleEl.checked = warmMicEnabled;
+}
+
+async function saveLiveTranscription(invokeFn, liveToggleEl) {
+  const previous = liveTranscriptionEnabled;
+  liveTranscriptionEnabled = liveToggleEl.checked;
+  try {
+    const config = await invokeFn("set_live_transcription", { enabled: liveTranscriptionEnabled });
+    liveTranscriptionEnabled = Boolean(config?.general?.live_transcription);
+    currentLanguage = config?.general?.language || "en";
+  } catch (error) {
+    liveTranscriptionEnabled = previous;
+    showDictError(dictErrorMsgEl, `Could not change live preview: ${error}`);
+  }
+  liveToggleEl.checked = liveTranscriptionEnabled;
+}
+
+async function saveCarryContext(invokeFn, carryContextToggleEl) {
+  const previous = carryContextEnabled;
+  carryContextEnabled = carryContextToggleEl.checked;
+  try {
+    const config = await invokeFn("set_carry_context", { enabled: carryContextEnabled });
+    carryContextEnabled = Boolean(config?.general?.carry_context);
+  } catch (error) {
+    carryContextEnabled = previous;
+    showDictError(dictErrorMsgEl, `Could not change follow-up dictation: ${error}`);
+  }
+  carryContextToggleEl.checked = carryContextEnabled;
+}
+
+async function saveTranslate(invokeFn, translateToggleEl, languageStatusEl) {
+  const previous = translateEnabled;
+  translateEnabled = translateToggleEl.checked;
+  try {
+    const config = await invokeFn("set_translate", { enabled: translateEnabled });
+    translateEnabled = Boolean(config?.general?.translate);
+  } catch (error) {
+    translateEnabled = previous;
+    if (languageStatusEl) languageStatusEl.textContent = String(error);
+  }
+  translateToggleEl.checked = translateEnabled;
+}
+
+async function saveDecodingPreset(invokeFn, decodingSelectEl, languageStatusEl) {
+  const previous = decodingPreset;
+  try {
+    const config = await invokeFn("set_decoding_preset", { preset: decodingSelectEl.value });
+    decodingPreset = config?.decoding?.preset || decodingSelectEl.value;
+  } catch (error) {
+    decodingPreset = previous;
+    if (languageStatusEl) languageStatusEl.textContent = String(error);
+  }
+  decodingSelectEl.value = decodingPreset;
+}
+
+function formatBenchmarkRun(run) {
+  return `${run.threads} threads: ${run.real_time_factor.toFixed(2)}× real time`;
+}
+
+async function runBenchmark(invokeFn, benchmarkBtn, benchmarkResultEl, benchmarkApplyBtn) {
+  benchmarkBtn.disabled = true;
+  benchmarkApplyBtn?.classList.add("hidden");
+  benchmarkResultEl.classList.remove("hidden");
+  benchmarkResultEl.textContent = "Benchmarking...";
+  try {
+    const report = await invokeFn("benchmark_model");
+    const runs = (report.runs || []).map(formatBenchmarkRun).join(", ");
+    const recommended = report.recommended_threads;
+    benchmarkResultEl.textContent = recommended
+      ? `${runs}. Recommended: ${recommended} threads (now ${report.current_threads}).`
+      : runs;
+    if (benchmarkApplyBtn && recommended && recommended !== report.current_threads) {
+      benchmarkApplyBtn.dataset.threads = String(recommended);
+      benchmarkApplyBtn.textContent = `Use ${recommended} Threads`;
+      benchmarkApplyBtn.classList.remove("hidden");
+    }
+  } catch (error) {
+    benchmarkResultEl.textContent = `Benchmark failed: ${error}`;
+  }
+  benchmarkBtn.disabled = false;
+}
+
+async function applyBenchmarkThreads(invokeFn, benchmarkResultEl, benchmarkApplyBtn) {
+  const threads = Number(benchmarkApplyBtn.dataset.threads || 0);
+  try {
+    await invokeFn("set_whisper_threads", { threads });
+    benchmarkResultEl.textContent = `Whisper now uses ${threads} threads.`;
+    benchmarkApplyBtn.classList.add("hidden");
+  } catch (error) {
+    benchmarkResultEl.textContent = `Could not save threads: ${error}`;
+  }
+}
+
+function applyModelStatus(setupMessageEl, downloadBtn, verifyBtn, status) {
+  const benchmarkBtn = document.getElementById("benchmark-btn");
+  if (!status.exists) {
+    setupMessageEl.textContent = `Model missing at ${status.path}`;
+    downloadBtn.classList.remove("hidden");
+    verifyBtn?.classList.add("hidden");
+    benchmarkBtn?.classList.add("hidden");
+    return;
+  }
+  verifyBtn?.classList.remove("hidden");
+  benchmarkBtn?.classList.remove("hidden");
+  if (status.valid === false) {
+    setupMessageEl.textContent = "The model file is corrupted (checksum mismatch). Download it again.";
+    downloadBtn.classList.remove("hidden");
+  } else {
+    setupMessageEl.textContent =
+      status.valid === true
+        ? "Model verified. Close this window and use the hotkey."
+        : "Model ready. Close this window and use the hotkey.";
+    downloadBtn.classList.add("hidden");
+  }
+}
+
+function formatDownloadProgress(pct, payload) {
+  const parts = [`${pct}%`];
+  const speed = Number(payload.bytes_per_sec || 0);
+  if (speed > 0) {
+    parts.push(`${(speed / 1_000_000).toFixed(1)} MB/s`);
+  }
+  if (payload.eta_secs != null && pct < 100) {
+    const secs = Math.ceil(Number(payload.eta_secs));
+    const minutes = Math.floor(secs / 60);
+    parts.push(`${minutes}:${String(secs % 60).padStart(2, "0")} left`);
+  }
+  return parts.join(" · ");
+}
+
 function applyModelUI(modelButtons, modelStatusEl, downloadBtn, modelName, confirmed) {
   modelButtons.forEach((btn) => {
     const isActive = btn.dataset.model === modelName;
@@ -273,6 +469,39 @@ function applyModelUI(modelButtons, modelStatusEl, downloadBtn, modelName, confi
   downloadBtn.textContent = size ? `Download Model (${size})` : "Download Model";
 }
 
+function applyLanguageUI(languageSelectEl, languageStatusEl, detected) {
+  if (!languageSelectEl) return;
+  // Keep languages set in config.toml selectable even without a preset option.
+  if (!Array.from(languageSelectEl.options).some((option) => option.value === currentLanguage)) {
+    languageSelectEl.add(new Option(currentLanguage, currentLanguage));
+  }
+  languageSelectEl.value = currentLanguage;
+
+  if (!languageStatusEl) return;
+  if (detected?.code) {
+    const pct = Math.round(Number(detected.probability || 0) * 100);
+    languageStatusEl.textContent = `Last recording: detected "${detected.code}" (${pct}% confidence).`;
+  } else if (currentLanguage === LANGUAGE_AUTO) {
+    languageStatusEl.textContent = "The language is detected on every recording (multilingual models only).";
+  } else {
+    languageStatusEl.textContent = "English-only models (.en) need English.";
+  }
+}
+
+async function saveLanguage(invokeFn, languageSelectEl, languageStatusEl) {
+  const previous = currentLanguage;
+  const language = languageSelectEl.value;
+  try {
+    const config = await invokeFn("set_language", { language });
+    currentLanguage = config?.general?.language || language;
+    applyLanguageUI(languageSelectEl, languageStatusEl);
+  } catch (error) {
+    currentLanguage = previous;
+    applyLanguageUI(languageSelectEl, languageStatusEl);
+    if (languageStatusEl) languageStatusEl.textContent = String(error);
+  }
+}
+
 async function saveModel(invokeFn, modelButtons, modelStatusEl, setupMessageEl, downloadBtn, modelName) {
   if (modelName === currentModel) return;
 
@@ -297,6 +526,23 @@ async function saveModel(invokeFn, modelButtons, modelStatusEl, setupMessageEl,
   }
 }
 
+async function importModel(invokeFn, modelButtons, modelStatusEl, setupMessageEl, downloadBtn, importInputEl, importBtn) {
+  const path = importInputEl.value.trim();
+  if (!path) return;
+
+  importBtn.disabled = true;
+  modelStatusEl.textContent = "Importing model...";
+  try {
+    const info = await invokeFn("import_model", { path });
+    importInputEl.value = "";
+    await saveModel(invokeFn, modelButtons, modelStatusEl, setupMessageEl, downloadBtn, info.name);
+  } catch (error) {
+    applyModelUI(modelButtons, modelStatusEl, downloadBtn, currentModel, true);
+    setupMessageEl.textContent = `Could not import model: ${error}`;
+  }
+  importBtn.disabled = false;
+}
+
 async function loadConfig(
   invokeFn,
   modelButtons,
@@ -308,13 +554,26 @@ async function loadConfig(
   promptProviderButtons,
   promptApiKeyEl,
   promptApiVisibilityBtnEl,
+  warmMicToggleEl,
+  liveToggleEl,
+  carryContextToggleEl,
+  languageSelectEl,
+  languageStatusEl,
+  translateToggleEl,
+  decodingSelectEl,
 ) {
   try {
     const config = await invokeFn("get_config");
     currentModel = config?.model?.name || "base.en";
     vocabWords = config?.dictionary?.words || [];
+    pinnedWords = config?.dictionary?.pinned || [];
     vocabReplacements = config?.dictionary?.replacements || [];
     promptModeConfig = normalizePromptModeConfig(config?.prompt_mode);
+    warmMicEnabled = Boolean(config?.audio?.warm_mic);
+    liveTranscriptionEnabled = Boolean(config?.general?.live_transcription);
+    carryContextEnabled = Boolean(config?.general?.carry_context);
+    translateEnabled = Boolean(config?.general?.translate);
+    decodingPreset = config?.decoding?.preset || "balanced";
   } catch (_) {
     // ignore — applyModelUI will use the default
   }
@@ -323,6 +582,22 @@ async function loadConfig(
   renderVocabList(vocabListEl);
   renderReplacementsList(replacementsListEl);
   applyPromptModeUI(promptToggleEl, promptProviderButtons, promptApiKeyEl, promptApiVisibilityBtnEl);
+  if (warmMicToggleEl) {
+    warmMicToggleEl.checked = warmMicEnabled;
+  }
+  if (liveToggleEl) {
+    liveToggleEl.checked = liveTranscriptionEnabled;
+  }
+  if (carryContextToggleEl) {
+    carryContextToggleEl.checked = carryContextEnabled;
+  }
+  applyLanguageUI(languageSelectEl, languageStatusEl);
+  if (translateToggleEl) {
+    translateToggleEl.checked = translateEnabled;
+  }
+  if (decodingSelectEl) {
+    decodingSelectEl.value = decodingPreset;
+  }
 }
 
 export async function initSetupView(invokeFn, listen) {
@@ -335,16 +610,32 @@ export async function initSetupView(invokeFn, listen) {
   const progressWrap = document.getElementById("progress-wrap");
   const progressEl = document.getElementById("progress");
   const progressTextEl = document.getElementById("progress-text");
+  const downloadCancelBtn = document.getElementById("download-cancel-btn");
+  const verifyBtn = document.getElementById("verify-btn");
+  const benchmarkBtn = document.getElementById("benchmark-btn");
+  const benchmarkResultEl = document.getElementById("benchmark-result");
+  const benchmarkApplyBtn = document.getElementById("benchmark-apply-btn");
   const modelStatusEl = document.getElementById("model-status");
+  const modelImportInputEl = document.getElementById("model-import-input");
+  const modelImportBtn = document.getElementById("model-import-btn");
   const hotkeyHintEl = document.getElementById("hotkey-hint");
   const modelButtons = Array.from(document.querySelectorAll("#model-switch .mode-btn"));
   const promptToggleEl = document.getElementById("prompt-mode-enabled");
   const promptProviderButtons = Array.from(document.querySelectorAll("#prompt-provider-switch .provider-btn"));
   const promptApiKeyEl = document.getElementById("prompt-api-key");
   const promptApiVisibilityBtnEl = document.getElementById("prompt-api-visibility-btn");
+  const warmMicToggleEl = document.getElementById("warm-mic-enabled");
+  const liveToggleEl = document.getElementById("live-transcription-enabled");
+  const carryContextToggleEl = document.getElementById("carry-context-enabled");
+  const languageSelectEl = document.getElementById("language-select");
+  const languageStatusEl = document.getElementById("language-status");
+  const translateToggleEl = document.getElementById("translate-enabled");
+  const decodingSelectEl = document.getElementById("decoding-select");
   const vocabListEl = document.getElementById("vocab-list");
   const vocabInputEl = document.getElementById("vocab-input");
   const vocabAddBtnEl = document.getElementById("vocab-add-btn");
+  const promptPreviewBtn = document.getElementById("prompt-preview-btn");
+  const promptPreviewEl = document.getElementById("prompt-preview");
   const replacementsListEl = document.getElementById("replacements-list");
   const replacementFromEl = document.getElementById("replacement-from-input");
   const replacementToEl = document.getElementById("replacement-to-input");
@@ -366,6 +657,13 @@ export async function initSetupView(invokeFn, listen) {
       promptProviderButtons,
       promptApiKeyEl,
       promptApiVisibilityBtnEl,
+      warmMicToggleEl,
+      liveToggleEl,
+      carryContextToggleEl,
+      languageSelectEl,
+      languageStatusEl,
+      translateToggleEl,
+      decodingSelectEl,
     ),
     invokeFn("check_model").catch((error) => ({ error })),
   ]);
@@ -437,6 +735,39 @@ export async function initSetupView(invokeFn, listen) {
     });
   }
 
+  if (warmMicToggleEl) {
+    warmMicToggleEl.addEventListener("change", () => saveWarmMic(invokeFn, warmMicToggleEl));
+  }
+
+  if (liveToggleEl) {
+    liveToggleEl.addEventListener("change", () => saveLiveTranscription(invokeFn, liveToggleEl));
+  }
+
+  if (carryContextToggleEl) {
+    carryContextToggleEl.addEventListener("change", () => saveCarryContext(invokeFn, carryContextToggleEl));
+  }
+
+  if (languageSelectEl) {
+    languageSelectEl.addEventListener("change", () => saveLanguage(invokeFn, languageSelectEl, languageStatusEl));
+  }
+
+  if (modelImportBtn && modelImportInputEl) {
+    const runImport = () =>
+      importModel(invokeFn, modelButtons, modelStatusEl, setupMessageEl, downloadBtn, modelImportInputEl, modelImportBtn);
+    modelImportBtn.addEventListener("click", runImport);
+    modelImportInputEl.addEventListener("keydown", (e) => {
+      if (e.key === "Enter") runImport();
+    });
+  }
+
+  if (decodingSelectEl) {
+    decodingSelectEl.addEventListener("change", () => saveDecodingPreset(invokeFn, decodingSelectEl, languageStatusEl));
+  }
+
+  if (translateToggleEl) {
+    translateToggleEl.addEventListener("change", () => saveTranslate(invokeFn, translateToggleEl, languageStatusEl));
+  }
+
   if (vocabAddBtnEl && vocabInputEl) {
     vocabAddBtnEl.addEventListener("click", () => {
       addVocabWord(invokeFn, vocabListEl, dictErrorMsgEl, vocabInputEl.value);
@@ -450,6 +781,10 @@ export async function initSetupView(invokeFn, listen) {
     });
   }
 
+  if (promptPreviewBtn && promptPreviewEl) {
+    promptPreviewBtn.addEventListener("click", () => previewInitialPrompt(invokeFn, promptPreviewEl));
+  }
+
   if (replacementAddBtnEl && replacementFromEl && replacementToEl) {
     replacementAddBtnEl.addEventListener("click", () => {
       addReplacement(invokeFn, replacementsListEl, dictErrorMsgEl, replacementFromEl.value, replacementToEl.value);
@@ -467,34 +802,74 @@ export async function initSetupView(invokeFn, listen) {
 
   if (modelResult && modelResult.error) {
     setupMessageEl.textContent = `Error checking model: ${modelResult.error}`;
-  } else if (modelResult && modelResult.exists) {
-    setupMessageEl.textContent = "Model ready. Close this window and use the hotkey.";
-    downloadBtn.classList.add("hidden");
   } else if (modelResult) {
-    setupMessageEl.textContent = `Model missing at ${modelResult.path}`;
-    downloadBtn.classList.remove("hidden");
+    applyModelStatus(setupMessageEl, downloadBtn, verifyBtn, modelResult);
   }
 
   downloadBtn.addEventListener("click", async () => {
     downloadBtn.disabled = true;
     progressWrap.classList.remove("hidden");
+    downloadCancelBtn?.classList.remove("hidden");
     setupMessageEl.textContent = "Downloading model...";
 
     try {
       await invokeFn("download_model");
       setupMessageEl.textContent = "Model downloaded. Ready to transcribe.";
       downloadBtn.classList.add("hidden");
+      verifyBtn?.classList.remove("hidden");
     } catch (error) {
-      setupMessageEl.textContent = `Download failed: ${error}`;
+      setupMessageEl.textContent = String(error).includes("cancelled")
+        ? "Download paused. Download again to resume."
+        : `Download failed: ${error}`;
       downloadBtn.disabled = false;
     }
+    downloadCancelBtn?.classList.add("hidden");
+  });
+
+  downloadCancelBtn?.addEventListener("click", () => {
+    invokeFn("cancel_model_download").catch(() => {});
+  });
+
+  verifyBtn?.addEventListener("click", async () => {
+    verifyBtn.disabled = true;
+    setupMessageEl.textContent = "Verifying model...";
+    try {
+      await invokeFn("verify_model");
+    } catch (error) {
+      setupMessageEl.textContent = `Could not verify model: ${error}`;
+    }
+    verifyBtn.disabled = false;
+  });
+
+  benchmarkBtn?.addEventListener("click", () => {
+    runBenchmark(invokeFn, benchmarkBtn, benchmarkResultEl, benchmarkApplyBtn);
+  });
+
+  benchmarkApplyBtn?.addEventListener("click", () => {
+    applyBenchmarkThreads(invokeFn, benchmarkResultEl, benchmarkApplyBtn);
+  });
+
+  await listen("benchmark_progress", (event) => {
+    if (event.payload) {
+      benchmarkResultEl.textContent = `Benchmarking... ${formatBenchmarkRun(event.payload)}`;
+    }
+  });
+
+  await listen("model_verified", (event) => {
+    applyModelStatus(setupMessageEl, downloadBtn, verifyBtn, event.payload || {});
+  });
+
+  await listen("status", (event) => {
+    if (event.payload?.detected_language) {
+      applyLanguageUI(languageSelectEl, languageStatusEl, event.payload.detected_language);
+    }
   });
 
   await listen("model_download_progress", (event) => {
-    const progress = Number(event.payload || 0);
-    const pct = Math.round(progress * 100);
+    const payload = event.payload || {};
+    const pct = Math.round(Number(payload.progress || 0) * 100);
     progressEl.style.width = `${pct}%`;
-    progressTextEl.textContent = `${pct}%`;
+    progressTextEl.textContent = formatDownloadProgress(pct, payload);
     progressWrap.setAttribute("aria-valuenow", pct);
   });
 
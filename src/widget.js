@@ -15,6 +15,8 @@ const BAR_AMBIENT_BREATH = 0.008;
 const LEVEL_GAIN = 14.0;
 // Exponent < 0.5 is heavy compression — even moderate speech pushes bars high.
 const LEVEL_EXPONENT = 0.45;
+// The live transcript box fits ~3 lines; older text scrolls off the front.
+const LIVE_TRANSCRIPT_MAX_CHARS = 180;
 const PROMPT_PROVIDER_ANTHROPIC = "anthropic";
 const PROMPT_PROVIDER_OPENAI = "openai";
 const PROMPT_MODEL_DEFAULTS = {
@@ -58,6 +60,8 @@ let waveformAnimationFrame = 0;
 let lastWaveformTimestamp = 0;
 let waveformState = "idle";
 let isToggleMode = false;
+let stopCountdownDeadline = 0;
+let stopCountdownTimer = 0;
 let widgetActionPending = false;
 // Prompt mode config is read-only in widget — toggled via config panel
 let promptModeConfig = {
@@ -206,6 +210,64 @@ function stopWaveformAnimation() {
   lastWaveformTimestamp = 0;
 }
 
+// Shown before an automatic stop: trailing silence in auto mode (`auto_stop_countdown`,
+// null when speech cancels it) or the max recording length (`recording_limit_warning`).
+// The backend sends the remaining ms once; the label ticks down locally.
+function showStopCountdown(captionEl, remainingMs) {
+  hideStopCountdown(captionEl);
+  if (remainingMs == null) return;
+
+  stopCountdownDeadline = performance.now() + Number(remainingMs);
+  const tick = () => {
+    const left = Math.max(0, stopCountdownDeadline - performance.now());
+    captionEl.textContent = `Stopping in ${(left / 1000).toFixed(1)}s`;
+  };
+  tick();
+  captionEl.classList.remove("hidden");
+  stopCountdownTimer = window.setInterval(tick, 100);
+}
+
+function hideStopCountdown(captionEl) {
+  if (stopCountdownTimer) {
+    window.clearInterval(stopCountdownTimer);
+    stopCountdownTimer = 0;
+  }
+  captionEl.classList.add("hidden");
+  captionEl.textContent = "";
+}
+
+// Long recordings are transcribed in chunks; `transcription_progress` reports each one.
+function showTranscriptionProgress(captionEl, progress) {
+  hideStopCountdown(captionEl);
+  const done = Number(progress?.done || 0);
+  const total = Number(progress?.total || 0);
+  if (!total) return;
+  captionEl.textContent = `Transcribing ${done}/${total}`;
+  captionEl.classList.remove("hidden");
+}
+
+// `partial_transcript` while recording: committed text is final, tentative text may
+// still change on the next pass and is shown dimmed.
+function showLiveTranscript(liveEls, payload) {
+  let committed = String(payload?.committed || "");
+  let tentative = String(payload?.tentative || "");
+  const overflow = committed.length + tentative.length - LIVE_TRANSCRIPT_MAX_CHARS;
+  if (overflow > 0) {
+    const cut = Math.min(overflow, committed.length);
+    committed = `…${committed.slice(cut)}`;
+    tentative = overflow > cut ? `…${tentative.slice(overflow - cut)}` : tentative;
+  }
+  liveEls.committed.textContent = committed;
+  liveEls.tentative.textContent = tentative;
+  liveEls.root.classList.toggle("hidden", !committed && !tentative);
+}
+
+function hideLiveTranscript(liveEls) {
+  liveEls.root.classList.add("hidden");
+  liveEls.committed.textContent = "";
+  liveEls.tentative.textContent = "";
+}
+
 function setWidgetButtonsEnabled(stopBtn, cancelBtn, enabled) {
   stopBtn.disabled = !enabled;
   cancelBtn.disabled = !enabled;
@@ -281,6 +343,12 @@ export async function initWidgetView(invoke, listen) {
   const waveformEl = document.getElementById("waveform");
   const stopBtn = document.getElementById("stop-btn");
   const cancelBtn = document.getElementById("cancel-btn");
+  const captionEl = document.getElementById("pill-caption");
+  const liveEls = {
+    root: document.getElementById("live-transcript"),
+    committed: document.getElementById("live-committed"),
+    tentative: document.getElementById("live-tentative"),
+  };
   setupEl.classList.add("hidden");
   widgetEl.classList.remove("hidden");
 
@@ -305,6 +373,11 @@ export async function initWidgetView(invoke, listen) {
     }),
     listen("status", (event) => {
       const payload = event.payload || {};
+      hideStopCountdown(captionEl);
+      // Keep the live transcript up while the rest is transcribed; clear it otherwise.
+      if (payload.status !== "processing") {
+        hideLiveTranscript(liveEls);
+      }
       setWidgetStatus(pill, waveformEl, stopBtn, cancelBtn, payload.status || "idle", payload.message || "");
     }),
     listen("toggle_mode_active", () => {
@@ -315,6 +388,22 @@ export async function initWidgetView(invoke, listen) {
       stopBtn.tabIndex = 0;
       cancelBtn.tabIndex = 0;
     }),
+    listen("auto_stop_countdown", (event) => {
+      showStopCountdown(captionEl, event.payload);
+    }),
+    listen("recording_limit_warning", (event) => {
+      showStopCountdown(captionEl, event.payload);
+    }),
+    listen("transcription_progress", (event) => {
+      if (waveformState === "processing") {
+        showTranscriptionProgress(captionEl, event.payload);
+      }
+    }),
+    listen("partial_transcript", (event) => {
+      if (waveformState === "recording") {
+        showLiveTranscript(liveEls, event.payload);
+      }
+    }),
     listen("model_ready", () => {
       console.log("Model pre-loaded and ready");
     }),